@@ -1,33 +1,136 @@
 use crate::expr::{CallableImpl, LiteralValue, NativeFunctionImpl};
+use crate::stdlib::{fs, math, number, process, string, NativeFn};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Clone)]
 pub struct Environment {
     pub values: Rc<RefCell<HashMap<String, LiteralValue>>>,
+    // The names in `values` that were defined with `const`
+    constants: Rc<RefCell<HashSet<String>>>,
     locals: Rc<RefCell<HashMap<usize, usize>>>,
     pub enclosing: Option<Box<Environment>>,
 }
 
-fn clock_impl(_args: &Vec<LiteralValue>) -> LiteralValue {
+fn clock_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
         .expect("Could not get system time")
         .as_millis();
 
-    LiteralValue::Number(now as f64 / 1000.0)
+    Ok(LiteralValue::Number(now as f64 / 1000.0))
 }
 
-fn get_globals() -> Rc<RefCell<HashMap<String, LiteralValue>>> {
-    let mut env = HashMap::new();
+fn assert_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    if args[0].is_truthy() == LiteralValue::True {
+        return Ok(LiteralValue::Nil);
+    }
+
+    match &args[1] {
+        LiteralValue::StringValue(msg) => Err(format!("Assertion failed: {msg}")),
+        other => Err(format!("Assertion failed: {}", other.to_string())),
+    }
+}
+
+fn assert_equal_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    if args[0] == args[1] {
+        Ok(LiteralValue::Nil)
+    } else {
+        Err(format!(
+            "Assertion failed: expected {} but got {}",
+            args[1].to_string(),
+            args[0].to_string()
+        ))
+    }
+}
+
+fn args_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::from_list(vec![]))
+}
+
+fn script_path_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::Nil)
+}
+
+fn define_native(env: &mut HashMap<String, LiteralValue>, name: &str, arity: usize, fun: NativeFn) {
     let fun_impl = NativeFunctionImpl {
-        name: "clock".to_string(),
-        arity: 0,
-        fun: Rc::new(clock_impl),
+        name: name.to_string(),
+        arity,
+        fun: Rc::new(fun),
     };
     let callable_impl = CallableImpl::NativeFunction(fun_impl);
-    env.insert("clock".to_string(), LiteralValue::Callable(callable_impl));
+    env.insert(name.to_string(), LiteralValue::Callable(callable_impl));
+}
+
+fn get_globals() -> Rc<RefCell<HashMap<String, LiteralValue>>> {
+    let mut env = HashMap::new();
+    define_native(&mut env, "clock", 0, clock_impl);
+    define_native(&mut env, "assert", 2, assert_impl);
+    define_native(&mut env, "assertEqual", 2, assert_equal_impl);
+    // Replaced by Interpreter::set_script_args when running a script
+    define_native(&mut env, "args", 0, args_impl);
+    define_native(&mut env, "scriptPath", 0, script_path_impl);
+
+    define_native(&mut env, "exit", 1, process::exit_impl);
+    define_native(&mut env, "env", 1, process::env_impl);
+    define_native(&mut env, "setEnv", 2, process::set_env_impl);
+    define_native(&mut env, "cwd", 0, process::cwd_impl);
+    define_native(&mut env, "chdir", 1, process::chdir_impl);
+    define_native(&mut env, "pid", 0, process::pid_impl);
+
+    define_native(&mut env, "readFile", 1, fs::read_file_impl);
+    define_native(&mut env, "writeFile", 2, fs::write_file_impl);
+    define_native(&mut env, "appendFile", 2, fs::append_file_impl);
+    define_native(&mut env, "exists", 1, fs::exists_impl);
+    define_native(&mut env, "isFile", 1, fs::is_file_impl);
+    define_native(&mut env, "isDir", 1, fs::is_dir_impl);
+    define_native(&mut env, "fileSize", 1, fs::file_size_impl);
+    define_native(&mut env, "modifiedTime", 1, fs::modified_time_impl);
+    define_native(&mut env, "listDir", 1, fs::list_dir_impl);
+    define_native(&mut env, "mkdir", 1, fs::mkdir_impl);
+    define_native(&mut env, "remove", 1, fs::remove_impl);
+    define_native(&mut env, "rename", 2, fs::rename_impl);
+    define_native(&mut env, "glob", 1, fs::glob_impl);
+    define_native(&mut env, "ioError", 0, fs::io_error_impl);
+
+    define_native(&mut env, "len", 1, string::len_impl);
+    define_native(&mut env, "split", 2, string::split_impl);
+    define_native(&mut env, "join", 2, string::join_impl);
+    define_native(&mut env, "trim", 1, string::trim_impl);
+    define_native(&mut env, "upper", 1, string::upper_impl);
+    define_native(&mut env, "lower", 1, string::lower_impl);
+    define_native(&mut env, "contains", 2, string::contains_impl);
+    define_native(&mut env, "startsWith", 2, string::starts_with_impl);
+    define_native(&mut env, "endsWith", 2, string::ends_with_impl);
+    define_native(&mut env, "replace", 3, string::replace_impl);
+    define_native(&mut env, "indexOf", 2, string::index_of_impl);
+    define_native(&mut env, "substring", 3, string::substring_impl);
+    define_native(&mut env, "repeat", 2, string::repeat_impl);
+    define_native(&mut env, "lines", 1, string::lines_impl);
+    define_native(&mut env, "chars", 1, string::chars_impl);
+    // The list and map helpers are only methods, like xs.map(f) and
+    // m.keys(), so that their common names stay free for scripts
+
+    define_native(&mut env, "floor", 1, math::floor_impl);
+    define_native(&mut env, "ceil", 1, math::ceil_impl);
+    define_native(&mut env, "round", 1, math::round_impl);
+    define_native(&mut env, "abs", 1, math::abs_impl);
+    define_native(&mut env, "sqrt", 1, math::sqrt_impl);
+    define_native(&mut env, "pow", 2, math::pow_impl);
+    define_native(&mut env, "min", 2, math::min_impl);
+    define_native(&mut env, "max", 2, math::max_impl);
+    define_native(&mut env, "sin", 1, math::sin_impl);
+    define_native(&mut env, "cos", 1, math::cos_impl);
+    define_native(&mut env, "tan", 1, math::tan_impl);
+    define_native(&mut env, "log", 1, math::log_impl);
+    env.insert("pi".to_string(), LiteralValue::Number(std::f64::consts::PI));
+
+    define_native(&mut env, "toNumber", 1, number::to_number_impl);
+    define_native(&mut env, "toString", 1, number::to_string_impl);
+    define_native(&mut env, "format", 2, number::format_impl);
+    define_native(&mut env, "toHex", 1, number::to_hex_impl);
+    define_native(&mut env, "toBinary", 1, number::to_binary_impl);
 
     Rc::new(RefCell::new(env))
 }
@@ -36,6 +139,7 @@ impl Environment {
     pub fn new(locals: HashMap<usize, usize>) -> Self {
         Self {
             values: get_globals(),
+            constants: Rc::new(RefCell::new(HashSet::new())),
             locals: Rc::new(RefCell::new(locals)),
             enclosing: None,
         }
@@ -51,13 +155,27 @@ impl Environment {
     pub fn enclose(&self) -> Environment {
         Self {
             values: Rc::new(RefCell::new(HashMap::new())),
+            constants: Rc::new(RefCell::new(HashSet::new())),
             locals: self.locals.clone(),
             enclosing: Some(Box::new(self.clone())),
         }
     }
 
-    pub fn define(&self, name: String, value: LiteralValue) {
+    // Like assignments, redeclarations of constants are already rejected by
+    // the resolver, this also guards the globals that it does not know about
+    pub fn define(&self, name: String, value: LiteralValue) -> Result<(), String> {
+        if self.constants.borrow().contains(&name) {
+            return Err(format!("Cannot redeclare constant '{name}'"));
+        }
+
         self.values.borrow_mut().insert(name, value);
+        Ok(())
+    }
+
+    pub fn define_constant(&self, name: String, value: LiteralValue) -> Result<(), String> {
+        self.define(name.clone(), value)?;
+        self.constants.borrow_mut().insert(name);
+        Ok(())
     }
 
     pub fn get(&self, name: &str, expr_id: usize) -> Option<LiteralValue> {
@@ -101,51 +219,76 @@ impl Environment {
         }
     }
 
-    pub fn assign_global(&self, name: &str, value: LiteralValue) -> bool {
+    pub fn assign_global(&self, name: &str, value: LiteralValue) -> Result<bool, String> {
         self.assign_internal(name, value, None)
     }
 
-    pub fn assign(&self, name: &str, value: LiteralValue, expr_id: usize) -> bool {
+    // Ok(false) if the variable has not been declared. The resolver already
+    // rejects assignments to constants, this also guards the globals that
+    // it does not know about.
+    pub fn assign(&self, name: &str, value: LiteralValue, expr_id: usize) -> Result<bool, String> {
         // ! Important that this ID matches with the resolver
         let distance = self.locals.borrow().get(&expr_id).cloned();
         self.assign_internal(name, value, distance)
     }
 
-    fn assign_internal(&self, name: &str, value: LiteralValue, distance: Option<usize>) -> bool {
+    fn assign_here(&self, name: &str, value: LiteralValue) -> Result<bool, String> {
+        if self.constants.borrow().contains(name) {
+            return Err(format!("Cannot assign to constant '{name}'"));
+        }
+
+        Ok(self
+            .values
+            .borrow_mut()
+            .insert(name.to_string(), value)
+            .is_some())
+    }
+
+    fn assign_internal(
+        &self,
+        name: &str,
+        value: LiteralValue,
+        distance: Option<usize>,
+    ) -> Result<bool, String> {
         if let None = distance {
             match &self.enclosing {
                 Some(env) => env.assign_internal(name, value, distance),
-                None => match self.values.borrow_mut().insert(name.to_string(), value) {
-                    Some(_) => true,
-                    None => false,
-                },
+                None => self.assign_here(name, value),
             }
         } else {
             let distance = distance.unwrap();
             if distance == 0 {
-                self.values.borrow_mut().insert(name.to_string(), value);
-                true
+                self.assign_here(name, value)?;
+                Ok(true)
             } else {
                 match &self.enclosing {
                     None => panic!("Tried to define a variable in a too deep level"),
-                    Some(env) => env.assign_internal(name, value, Some(distance - 1)),
+                    Some(env) => env.assign_internal(name, value, Some(distance - 1))?,
                 };
-                true
+                Ok(true)
             }
         }
     }
 
-    #[allow(dead_code)]
+    // The bindings as `name = value` lines sorted by name, followed by those
+    // of the enclosing environments indented two more spaces each. Built-ins
+    // are left out, unless they have been redefined.
     pub fn dump(&self, indent: usize) -> String {
-        let mut result = String::new();
-        for (key, val) in self.values.borrow().iter() {
-            for _ in 0..indent {
-                result.push_str(" ");
-            }
-            result.push_str(&format!("{}: {:?}\n", key, val));
-        }
+        self.dump_without(&get_globals().borrow(), indent)
+    }
+
+    fn dump_without(&self, builtins: &HashMap<String, LiteralValue>, indent: usize) -> String {
+        let values = self.values.borrow();
+        let mut lines = values
+            .iter()
+            .filter(|(name, value)| builtins.get(*name) != Some(*value))
+            .map(|(name, value)| format!("{}{name} = {}\n", " ".repeat(indent), value.to_string()))
+            .collect::<Vec<String>>();
+        lines.sort();
+
+        let mut result = lines.concat();
         if let Some(env) = &self.enclosing {
-            result.push_str(&env.dump(indent + 2));
+            result.push_str(&env.dump_without(builtins, indent + 2));
         }
 
         result
@@ -159,4 +302,52 @@ mod tests {
     fn try_init() {
         let _environment = Environment::new(HashMap::new());
     }
+
+    #[test]
+    fn assign_constants() {
+        // The expression with id 0 assigns to the innermost scope
+        let environment = Environment::new(HashMap::from([(0, 0)]));
+        environment
+            .define_constant("answer".to_string(), LiteralValue::Number(42.0))
+            .unwrap();
+        assert_eq!(
+            environment.assign_global("answer", LiteralValue::Nil),
+            Err("Cannot assign to constant 'answer'".to_string())
+        );
+
+        let inner = environment.enclose();
+        assert_eq!(
+            inner.assign_global("answer", LiteralValue::Nil),
+            Err("Cannot assign to constant 'answer'".to_string())
+        );
+        inner
+            .define("answer".to_string(), LiteralValue::Nil)
+            .unwrap();
+        assert_eq!(inner.assign("answer", LiteralValue::True, 0), Ok(true));
+
+        assert_eq!(
+            environment.define("answer".to_string(), LiteralValue::Nil),
+            Err("Cannot redeclare constant 'answer'".to_string())
+        );
+        assert_eq!(
+            environment.define_constant("answer".to_string(), LiteralValue::Nil),
+            Err("Cannot redeclare constant 'answer'".to_string())
+        );
+    }
+
+    #[test]
+    fn dump_skips_builtins() {
+        let environment = Environment::new(HashMap::new());
+        assert_eq!(environment.dump(0), "");
+
+        environment
+            .define("pi".to_string(), LiteralValue::Number(3.0))
+            .unwrap();
+        environment
+            .define("b".to_string(), LiteralValue::True)
+            .unwrap();
+        let inner = environment.enclose();
+        inner.define("a".to_string(), LiteralValue::Nil).unwrap();
+        assert_eq!(inner.dump(0), "a = nil\n  b = true\n  pi = 3\n");
+    }
 }
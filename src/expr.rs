@@ -1,7 +1,11 @@
 use crate::environment::Environment;
 use crate::interpreter::Interpreter;
+use crate::pattern::Pattern;
 use crate::scanner;
 use crate::scanner::{Token, TokenType};
+use crate::stdlib::methods::bind_method;
+use crate::stdlib::number::format_number;
+use crate::stdlib::string::display;
 use std::cell::RefCell;
 use std::cmp::{Eq, PartialEq};
 use std::collections::HashMap;
@@ -24,11 +28,15 @@ pub struct LoxFunctionImpl {
     pub body: Vec<Box<Stmt>>,
 }
 
+// Natives are closures so that they can capture values, like the script
+// arguments returned by `args()`
+pub type NativeClosure = dyn Fn(&[LiteralValue]) -> Result<LiteralValue, String>;
+
 #[derive(Clone)]
 pub struct NativeFunctionImpl {
     pub name: String,
     pub arity: usize,
-    pub fun: Rc<dyn Fn(&Vec<LiteralValue>) -> LiteralValue>,
+    pub fun: Rc<NativeClosure>,
 }
 
 #[derive(Clone)]
@@ -49,6 +57,9 @@ pub enum LiteralValue {
         class: Box<LiteralValue>,
         fields: Rc<RefCell<Vec<(String, LiteralValue)>>>,
     },
+    List(Rc<RefCell<Vec<LiteralValue>>>),
+    // Entries keep their insertion order
+    Map(Rc<RefCell<Vec<(String, LiteralValue)>>>),
 }
 use LiteralValue::*;
 
@@ -60,6 +71,16 @@ impl std::fmt::Debug for LiteralValue {
 
 impl PartialEq for LiteralValue {
     fn eq(&self, other: &Self) -> bool {
+        self.equals(other, &mut vec![])
+    }
+}
+
+// Lists and maps can contain themselves, so comparing and printing them
+// keeps track of the ones it is already inside of
+impl LiteralValue {
+    // Two lists or maps that are compared again while comparing them are
+    // equal if nothing else in them differs
+    fn equals(&self, other: &Self, visiting: &mut Vec<(*const (), *const ())>) -> bool {
         match (self, other) {
             (Number(x), Number(y)) => x == y,
             (
@@ -79,6 +100,34 @@ impl PartialEq for LiteralValue {
                 })),
             ) => name == name2 && arity == arity2,
             (StringValue(x), StringValue(y)) => x == y,
+            (List(x), List(y)) => {
+                let pair = (Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ());
+                if Rc::ptr_eq(x, y) || visiting.contains(&pair) {
+                    return true;
+                }
+
+                visiting.push(pair);
+                let (x, y) = (x.borrow(), y.borrow());
+                let equal = x.len() == y.len()
+                    && x.iter().zip(y.iter()).all(|(a, b)| a.equals(b, visiting));
+                visiting.pop();
+                equal
+            }
+            (Map(x), Map(y)) => {
+                let pair = (Rc::as_ptr(x) as *const (), Rc::as_ptr(y) as *const ());
+                if Rc::ptr_eq(x, y) || visiting.contains(&pair) {
+                    return true;
+                }
+
+                visiting.push(pair);
+                let (x, y) = (x.borrow(), y.borrow());
+                let equal = x.len() == y.len()
+                    && x.iter().all(|(key, value)| {
+                        y.iter().any(|(k, v)| k == key && value.equals(v, visiting))
+                    });
+                visiting.pop();
+                equal
+            }
             (True, True) => true,
             (False, False) => true,
             (Nil, Nil) => true,
@@ -118,8 +167,13 @@ macro_rules! class_name {
 
 impl LiteralValue {
     pub fn to_string(&self) -> String {
+        self.display(&mut vec![])
+    }
+
+    // A list or map inside of itself is printed as [...] or {...}
+    fn display(&self, visiting: &mut Vec<*const ()>) -> String {
         match self {
-            LiteralValue::Number(x) => x.to_string(),
+            LiteralValue::Number(x) => format_number(*x),
             LiteralValue::StringValue(x) => format!("\"{}\"", x),
             LiteralValue::True => "true".to_string(),
             LiteralValue::False => "false".to_string(),
@@ -142,6 +196,36 @@ impl LiteralValue {
             LiteralValue::LoxInstance { class, fields: _ } => {
                 format!("Instance of '{}'", class_name!(class))
             }
+            LiteralValue::List(elements) => {
+                let ptr = Rc::as_ptr(elements) as *const ();
+                if visiting.contains(&ptr) {
+                    return "[...]".to_string();
+                }
+
+                visiting.push(ptr);
+                let elements = elements
+                    .borrow()
+                    .iter()
+                    .map(|element| element.display(visiting))
+                    .collect::<Vec<String>>();
+                visiting.pop();
+                format!("[{}]", elements.join(", "))
+            }
+            LiteralValue::Map(entries) => {
+                let ptr = Rc::as_ptr(entries) as *const ();
+                if visiting.contains(&ptr) {
+                    return "{...}".to_string();
+                }
+
+                visiting.push(ptr);
+                let entries = entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("\"{}\": {}", key, value.display(visiting)))
+                    .collect::<Vec<String>>();
+                visiting.pop();
+                format!("{{{}}}", entries.join(", "))
+            }
         }
     }
 
@@ -159,13 +243,17 @@ impl LiteralValue {
                 superclass: _,
             } => "Class",
             LiteralValue::LoxInstance { class, fields: _ } => &class_name!(class),
+            LiteralValue::List(_) => "List",
+            LiteralValue::Map(_) => "Map",
         }
     }
 
     pub fn from_token(token: Token) -> Self {
         match token.token_type {
             TokenType::Number => Self::Number(unwrap_as_f64(token.literal)),
-            TokenType::StringLit => Self::StringValue(unwrap_as_string(token.literal)),
+            TokenType::StringLit | TokenType::Interpolation => {
+                Self::StringValue(unwrap_as_string(token.literal))
+            }
             TokenType::False => Self::False,
             TokenType::True => Self::True,
             TokenType::Nil => Self::Nil,
@@ -173,6 +261,14 @@ impl LiteralValue {
         }
     }
 
+    pub fn from_list(elements: Vec<LiteralValue>) -> Self {
+        List(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn from_map(entries: Vec<(String, LiteralValue)>) -> Self {
+        Map(Rc::new(RefCell::new(entries)))
+    }
+
     pub fn from_bool(b: bool) -> Self {
         if b {
             True
@@ -197,6 +293,8 @@ impl LiteralValue {
                     False
                 }
             }
+            List(elements) => LiteralValue::from_bool(elements.borrow().is_empty()),
+            Map(entries) => LiteralValue::from_bool(entries.borrow().is_empty()),
             True => False,
             False => True,
             Nil => True,
@@ -222,6 +320,8 @@ impl LiteralValue {
                     True
                 }
             }
+            List(elements) => LiteralValue::from_bool(!elements.borrow().is_empty()),
+            Map(entries) => LiteralValue::from_bool(!entries.borrow().is_empty()),
             True => True,
             False => False,
             Nil => False,
@@ -232,7 +332,15 @@ impl LiteralValue {
     }
 }
 
-use crate::stmt::Stmt;
+use crate::stmt::{params_to_string, stmts_to_string, Stmt};
+
+// pattern if guard => body
+#[derive(Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Expr,
+}
 
 #[derive(Clone)]
 pub enum Expr {
@@ -256,11 +364,45 @@ pub enum Expr {
     // 2 + 2 |> f
     Call {
         // x |> f -> Call { id, f, paren (pipe), arguments: [x]}
+        // `paren` is the opening paren of f(x)
         id: usize,
         callee: Box<Expr>,
         paren: Token,
         arguments: Vec<Expr>,
     },
+    // name += value, or ++name and name++ with a value of 1. `operator` is
+    // the binary operator applied, and `postfix` gives the old value
+    CompoundAssign {
+        id: usize,
+        name: Token,
+        operator: Token,
+        value: Box<Expr>,
+        postfix: bool,
+    },
+    CompoundSet {
+        id: usize,
+        object: Box<Expr>,
+        name: Token,
+        operator: Token,
+        value: Box<Expr>,
+        postfix: bool,
+    },
+    CompoundIndexSet {
+        id: usize,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+        postfix: bool,
+    },
+    // condition ? then_branch : else_branch
+    Conditional {
+        id: usize,
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
     Get {
         id: usize,
         object: Box<Expr>,
@@ -270,22 +412,55 @@ pub enum Expr {
         id: usize,
         expression: Box<Expr>,
     },
+    Index {
+        id: usize,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    // "a ${b} c" concatenates the parts as strings
+    Interpolation {
+        id: usize,
+        parts: Vec<Expr>,
+    },
+    List {
+        id: usize,
+        elements: Vec<Expr>,
+    },
     Literal {
         id: usize,
         value: LiteralValue,
     },
+    Map {
+        id: usize,
+        entries: Vec<(String, Expr)>,
+    },
     Logical {
         id: usize,
         left: Box<Expr>,
         operator: Token,
         right: Box<Expr>,
     },
+    // The first arm whose pattern matches the value and whose guard holds
+    Match {
+        id: usize,
+        keyword: Token,
+        value: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
     Set {
         id: usize,
         object: Box<Expr>,
         name: Token,
         value: Box<Expr>,
     },
+    SetIndex {
+        id: usize,
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
     This {
         id: usize,
         keyword: Token,
@@ -355,12 +530,51 @@ impl Expr {
                 paren: _,
                 arguments: _,
             } => *id,
+            Expr::CompoundAssign {
+                id,
+                name: _,
+                operator: _,
+                value: _,
+                postfix: _,
+            } => *id,
+            Expr::CompoundSet {
+                id,
+                object: _,
+                name: _,
+                operator: _,
+                value: _,
+                postfix: _,
+            } => *id,
+            Expr::CompoundIndexSet {
+                id,
+                object: _,
+                bracket: _,
+                index: _,
+                operator: _,
+                value: _,
+                postfix: _,
+            } => *id,
+            Expr::Conditional {
+                id,
+                condition: _,
+                then_branch: _,
+                else_branch: _,
+            } => *id,
             Expr::Get {
                 id,
                 object: _,
                 name: _,
             } => *id,
             Expr::Grouping { id, expression: _ } => *id,
+            Expr::Index {
+                id,
+                object: _,
+                bracket: _,
+                index: _,
+            } => *id,
+            Expr::Interpolation { id, parts: _ } => *id,
+            Expr::List { id, elements: _ } => *id,
+            Expr::Map { id, entries: _ } => *id,
             Expr::Literal { id, value: _ } => *id,
             Expr::Logical {
                 id,
@@ -368,12 +582,25 @@ impl Expr {
                 operator: _,
                 right: _,
             } => *id,
+            Expr::Match {
+                id,
+                keyword: _,
+                value: _,
+                arms: _,
+            } => *id,
             Expr::Set {
                 id,
                 object: _,
                 name: _,
                 value: _,
             } => *id,
+            Expr::SetIndex {
+                id,
+                object: _,
+                bracket: _,
+                index: _,
+                value: _,
+            } => *id,
             Expr::This { id, keyword: _ } => *id,
             Expr::Super {
                 id,
@@ -398,9 +625,15 @@ impl Expr {
                 id: _,
                 paren: _,
                 arguments,
-                body: _,
-            } => format!("anon/{}", arguments.len()),
-            Expr::Assign { id: _, name, value } => format!("({name:?} = {}", value.to_string()),
+                body,
+            } => format!(
+                "(fun ({}) {})",
+                params_to_string(arguments),
+                stmts_to_string(body)
+            ),
+            Expr::Assign { id: _, name, value } => {
+                format!("(= {} {})", name.lexeme, value.to_string())
+            }
             Expr::Binary {
                 id: _,
                 left,
@@ -417,7 +650,66 @@ impl Expr {
                 callee,
                 paren: _,
                 arguments,
-            } => format!("({} {:?})", (*callee).to_string(), arguments),
+            } => {
+                let mut parts = vec![(*callee).to_string()];
+                parts.extend(arguments.iter().map(|arg| arg.to_string()));
+                format!("(call {})", parts.join(" "))
+            }
+            Expr::CompoundAssign {
+                id: _,
+                name,
+                operator,
+                value,
+                postfix,
+            } => format!(
+                "({}= {} {}{})",
+                operator.lexeme,
+                name.lexeme,
+                value.to_string(),
+                if *postfix { " postfix" } else { "" }
+            ),
+            Expr::CompoundSet {
+                id: _,
+                object,
+                name,
+                operator,
+                value,
+                postfix,
+            } => format!(
+                "(set{}= {} {} {}{})",
+                operator.lexeme,
+                object.to_string(),
+                name.lexeme,
+                value.to_string(),
+                if *postfix { " postfix" } else { "" }
+            ),
+            Expr::CompoundIndexSet {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                operator,
+                value,
+                postfix,
+            } => format!(
+                "(set-index{}= {} {} {}{})",
+                operator.lexeme,
+                object.to_string(),
+                index.to_string(),
+                value.to_string(),
+                if *postfix { " postfix" } else { "" }
+            ),
+            Expr::Conditional {
+                id: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => format!(
+                "(? {} {} {})",
+                condition.to_string(),
+                then_branch.to_string(),
+                else_branch.to_string()
+            ),
             Expr::Get {
                 id: _,
                 object,
@@ -426,6 +718,30 @@ impl Expr {
             Expr::Grouping { id: _, expression } => {
                 format!("(group {})", (*expression).to_string())
             }
+            Expr::Index {
+                id: _,
+                object,
+                bracket: _,
+                index,
+            } => format!("(index {} {})", object.to_string(), index.to_string()),
+            Expr::Interpolation { id: _, parts } => {
+                let mut strings = vec!["str".to_string()];
+                strings.extend(parts.iter().map(|part| part.to_string()));
+                format!("({})", strings.join(" "))
+            }
+            Expr::List { id: _, elements } => {
+                let mut parts = vec!["list".to_string()];
+                parts.extend(elements.iter().map(|element| element.to_string()));
+                format!("({})", parts.join(" "))
+            }
+            Expr::Map { id: _, entries } => {
+                let mut parts = vec!["map".to_string()];
+                for (key, value) in entries {
+                    parts.push(format!("\"{}\"", key));
+                    parts.push(value.to_string());
+                }
+                format!("({})", parts.join(" "))
+            }
             Expr::Literal { id: _, value } => format!("{}", value.to_string()),
             Expr::Logical {
                 id: _,
@@ -434,10 +750,31 @@ impl Expr {
                 right,
             } => format!(
                 "({} {} {})",
-                operator.to_string(),
+                operator.lexeme,
                 left.to_string(),
                 right.to_string()
             ),
+            Expr::Match {
+                id: _,
+                keyword: _,
+                value,
+                arms,
+            } => {
+                let mut parts = vec![value.to_string()];
+                for arm in arms {
+                    let guard = match &arm.guard {
+                        Some(guard) => format!(" (if {})", guard.to_string()),
+                        None => "".to_string(),
+                    };
+                    parts.push(format!(
+                        "(arm {}{} {})",
+                        arm.pattern,
+                        guard,
+                        arm.body.to_string()
+                    ));
+                }
+                format!("(match {})", parts.join(" "))
+            }
             Expr::Set {
                 id: _,
                 object,
@@ -446,7 +783,19 @@ impl Expr {
             } => format!(
                 "(set {} {} {})",
                 object.to_string(),
-                name.to_string(),
+                name.lexeme,
+                value.to_string()
+            ),
+            Expr::SetIndex {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => format!(
+                "(set-index {} {} {})",
+                object.to_string(),
+                index.to_string(),
                 value.to_string()
             ),
             Expr::This { id: _, keyword: _ } => format!("(this)"),
@@ -494,7 +843,7 @@ impl Expr {
             Expr::Assign { id: _, name, value } => {
                 let new_value = (*value).evaluate(environment.clone())?;
                 let assign_success =
-                    environment.assign(&name.lexeme, new_value.clone(), self.get_id());
+                    environment.assign(&name.lexeme, new_value.clone(), self.get_id())?;
 
                 if assign_success {
                     Ok(new_value)
@@ -502,6 +851,101 @@ impl Expr {
                     Err(format!("Variable {} has not been declared", name.lexeme))
                 }
             }
+            Expr::CompoundAssign {
+                id: _,
+                name,
+                operator,
+                value,
+                postfix,
+            } => {
+                let old_value = match environment.get(&name.lexeme, self.get_id()) {
+                    Some(old_value) => old_value,
+                    None => return Err(format!("Variable {} has not been declared", name.lexeme)),
+                };
+                let value = value.evaluate(environment.clone())?;
+                let new_value = binary_op(&old_value, operator, &value)?;
+                environment.assign(&name.lexeme, new_value.clone(), self.get_id())?;
+
+                Ok(if *postfix { old_value } else { new_value })
+            }
+            Expr::CompoundSet {
+                id: _,
+                object,
+                name,
+                operator,
+                value,
+                postfix,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                if let LoxInstance { class: _, fields } = obj_value {
+                    let old_value = match get_field(&fields, &name.lexeme) {
+                        Some(old_value) => old_value,
+                        None => {
+                            return Err(format!("No field named {} on this instance", name.lexeme))
+                        }
+                    };
+                    let value = value.evaluate(environment.clone())?;
+                    let new_value = binary_op(&old_value, operator, &value)?;
+                    set_field(&fields, &name.lexeme, new_value.clone());
+
+                    Ok(if *postfix { old_value } else { new_value })
+                } else {
+                    Err(format!(
+                        "Cannot set property on type {}",
+                        obj_value.to_type()
+                    ))
+                }
+            }
+            Expr::CompoundIndexSet {
+                id: _,
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+                postfix,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                let (old_value, new_value) = match obj_value {
+                    List(elements) => {
+                        let idx = list_index(&index, elements.borrow().len(), "list", bracket)?;
+                        let old_value = elements.borrow()[idx].clone();
+                        let value = value.evaluate(environment.clone())?;
+                        let new_value = binary_op(&old_value, operator, &value)?;
+                        // The value may have changed the length of the list
+                        let idx = list_index(&index, elements.borrow().len(), "list", bracket)?;
+                        elements.borrow_mut()[idx] = new_value.clone();
+                        (old_value, new_value)
+                    }
+                    Map(entries) => {
+                        let key = map_key(&index, bracket)?;
+                        let old_value = entries
+                            .borrow()
+                            .iter()
+                            .find(|(k, _)| *k == key)
+                            .map(|(_, value)| value.clone())
+                            .unwrap_or(Nil);
+                        let value = value.evaluate(environment.clone())?;
+                        let new_value = binary_op(&old_value, operator, &value)?;
+                        let mut entries = entries.borrow_mut();
+                        match entries.iter_mut().find(|(k, _)| *k == key) {
+                            Some(entry) => entry.1 = new_value.clone(),
+                            None => entries.push((key, new_value.clone())),
+                        }
+                        (old_value, new_value)
+                    }
+                    other => {
+                        return Err(format!(
+                            "Line {}: Cannot index into type {}",
+                            bracket.line_number,
+                            other.to_type()
+                        ))
+                    }
+                };
+
+                Ok(if *postfix { old_value } else { new_value })
+            }
             Expr::Variable { id: _, name } => match environment.get(&name.lexeme, self.get_id()) {
                 Some(value) => Ok(value.clone()),
                 None => Err(format!(
@@ -513,7 +957,7 @@ impl Expr {
             Expr::Call {
                 id: _,
                 callee,
-                paren: _,
+                paren,
                 arguments,
             } => {
                 // Look up function definition in environment
@@ -525,52 +969,175 @@ impl Expr {
                         run_lox_function(loxfun, arguments, environment)
                     }
                     Callable(CallableImpl::NativeFunction(nativefun)) => {
+                        if arguments.len() != nativefun.arity {
+                            return Err(format!(
+                                "Callable {} expected {} arguments but got {}",
+                                nativefun.name,
+                                nativefun.arity,
+                                arguments.len()
+                            ));
+                        }
+
                         let mut evaluated_arguments = vec![];
                         for argument in arguments {
                             evaluated_arguments.push(argument.evaluate(environment.clone())?);
                         }
-                        Ok((nativefun.fun)(&evaluated_arguments))
+                        (nativefun.fun)(&evaluated_arguments)
+                            .map_err(|msg| format!("Line {}: {}", paren.line_number, msg))
                     }
-                    LoxClass {
-                        name: _,
-                        methods,
-                        superclass: _,
-                    } => {
-                        let instance = LoxInstance {
-                            class: Box::new(callable_clone.clone()),
-                            fields: Rc::new(RefCell::new(vec![])),
-                        };
-
-                        // Call constructor if present
-                        if let Some(init_method) = methods.get("init") {
-                            if init_method.arity != arguments.len() {
-                                return Err(
-                                    "Invalid number of arguments in constructor".to_string()
-                                );
-                            }
-
-                            // let new_env = environment.enclose();
-                            // new_env.define("this".to_string(), instance.clone());
-                            // let mut init_method = init_method.clone();
-                            // init_method.parent_env = new_env.clone();
-                            let mut init_method = init_method.clone();
-                            init_method.parent_env = init_method.parent_env.enclose();
-                            init_method
-                                .parent_env
-                                .define("this".to_string(), instance.clone());
-
-                            if let Err(msg) = run_lox_function(init_method, arguments, environment)
-                            {
-                                return Err(msg);
-                            }
+                    LoxClass { .. } => {
+                        let mut evaluated_arguments = vec![];
+                        for argument in arguments {
+                            evaluated_arguments.push(argument.evaluate(environment.clone())?);
                         }
-
-                        Ok(instance)
+                        instantiate(&callable_clone, evaluated_arguments)
                     }
                     other => Err(format!("{} is not callable", other.to_type())),
                 }
             }
+            Expr::Conditional {
+                id: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                // Only the chosen branch is evaluated
+                if condition.evaluate(environment.clone())?.is_truthy() == True {
+                    then_branch.evaluate(environment)
+                } else {
+                    else_branch.evaluate(environment)
+                }
+            }
             Expr::Literal { id: _, value } => Ok((*value).clone()),
+            Expr::Match {
+                id: _,
+                keyword,
+                value,
+                arms,
+            } => {
+                let value = value.evaluate(environment.clone())?;
+                for arm in arms {
+                    let mut bound = vec![];
+                    if arm.pattern.bind(&value, &mut bound).is_err() {
+                        continue;
+                    }
+
+                    // Each arm has its own scope for the names it binds
+                    let arm_environment = environment.enclose();
+                    for (name, value) in bound {
+                        arm_environment.define(name, value)?;
+                    }
+                    if let Some(guard) = &arm.guard {
+                        if guard.evaluate(arm_environment.clone())?.is_truthy() != True {
+                            continue;
+                        }
+                    }
+
+                    return arm.body.evaluate(arm_environment);
+                }
+
+                Err(format!(
+                    "Line {}: No match arm matches {}",
+                    keyword.line_number,
+                    value.to_string()
+                ))
+            }
+            Expr::Interpolation { id: _, parts } => {
+                let mut result = String::new();
+                for part in parts {
+                    result.push_str(&display(&part.evaluate(environment.clone())?));
+                }
+                Ok(StringValue(result))
+            }
+            Expr::List { id: _, elements } => {
+                let mut values = vec![];
+                for element in elements {
+                    values.push(element.evaluate(environment.clone())?);
+                }
+                Ok(LiteralValue::from_list(values))
+            }
+            Expr::Map { id: _, entries } => {
+                let mut values: Vec<(String, LiteralValue)> = vec![];
+                for (key, value) in entries {
+                    let value = value.evaluate(environment.clone())?;
+                    // A repeated key keeps its first position and its last value
+                    match values.iter_mut().find(|(k, _)| k == key) {
+                        Some(entry) => entry.1 = value,
+                        None => values.push((key.clone(), value)),
+                    }
+                }
+                Ok(LiteralValue::from_map(values))
+            }
+            Expr::Index {
+                id: _,
+                object,
+                bracket,
+                index,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                match obj_value {
+                    List(elements) => {
+                        let elements = elements.borrow();
+                        let idx = list_index(&index, elements.len(), "list", bracket)?;
+                        Ok(elements[idx].clone())
+                    }
+                    // Strings are indexed by char and give a one char String
+                    StringValue(s) => {
+                        let idx = list_index(&index, s.chars().count(), "string", bracket)?;
+                        let ch = s.chars().nth(idx).unwrap_or_default();
+                        Ok(StringValue(ch.to_string()))
+                    }
+                    // A missing key gives nil
+                    Map(entries) => {
+                        let key = map_key(&index, bracket)?;
+                        Ok(entries
+                            .borrow()
+                            .iter()
+                            .find(|(k, _)| *k == key)
+                            .map(|(_, value)| value.clone())
+                            .unwrap_or(Nil))
+                    }
+                    other => Err(format!(
+                        "Line {}: Cannot index into type {}",
+                        bracket.line_number,
+                        other.to_type()
+                    )),
+                }
+            }
+            Expr::SetIndex {
+                id: _,
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let obj_value = object.evaluate(environment.clone())?;
+                let index = index.evaluate(environment.clone())?;
+                match obj_value {
+                    List(elements) => {
+                        let value = value.evaluate(environment.clone())?;
+                        let idx = list_index(&index, elements.borrow().len(), "list", bracket)?;
+                        elements.borrow_mut()[idx] = value.clone();
+                        Ok(value)
+                    }
+                    Map(entries) => {
+                        let key = map_key(&index, bracket)?;
+                        let value = value.evaluate(environment.clone())?;
+                        let mut entries = entries.borrow_mut();
+                        match entries.iter_mut().find(|(k, _)| *k == key) {
+                            Some(entry) => entry.1 = value.clone(),
+                            None => entries.push((key, value.clone())),
+                        }
+                        Ok(value)
+                    }
+                    other => Err(format!(
+                        "Line {}: Cannot index into type {}",
+                        bracket.line_number,
+                        other.to_type()
+                    )),
+                }
+            }
             Expr::Logical {
                 id: _,
                 left,
@@ -605,11 +1172,9 @@ impl Expr {
                 let obj_value = object.evaluate(environment.clone())?;
                 // Now obj_value should be a LoxInstance
                 if let LoxInstance { class, fields } = obj_value.clone() {
-                    for (field_name, value) in (*fields.borrow()).iter() {
-                        // Are we getting a field on the object?
-                        if field_name == &name.lexeme {
-                            return Ok(value.clone());
-                        }
+                    // Are we getting a field on the object?
+                    if let Some(value) = get_field(&fields, &name.lexeme) {
+                        return Ok(value);
                     }
                     // Are we getting a method on the object?
                     // TODO Make a function that finds a method on a class by looking first at the
@@ -624,7 +1189,7 @@ impl Expr {
                         if let Some(method) = find_method(&name.lexeme, *class.clone()) {
                             let mut callable_impl = method.clone();
                             let new_env = callable_impl.parent_env.enclose();
-                            new_env.define("this".to_string(), obj_value.clone());
+                            new_env.define("this".to_string(), obj_value.clone())?;
                             callable_impl.parent_env = new_env;
                             return Ok(Callable(LoxFunction(callable_impl)));
                         }
@@ -632,9 +1197,13 @@ impl Expr {
                         panic!("The class field on an instance was not a LoxClass");
                     }
                     Err(format!("No field named {} on this instance", name.lexeme))
+                } else if let Some(method) = bind_method(&obj_value, &name.lexeme) {
+                    Ok(method)
                 } else {
                     Err(format!(
-                        "Cannot access property on type {}",
+                        "Line {}: Cannot access property {} on type {}",
+                        name.line_number,
+                        name.lexeme,
                         obj_value.to_type()
                     ))
                 }
@@ -648,23 +1217,7 @@ impl Expr {
                 let obj_value = object.evaluate(environment.clone())?;
                 if let LoxInstance { class: _, fields } = obj_value {
                     let value = value.evaluate(environment.clone())?;
-
-                    let mut idx = 0;
-                    let mut found = false;
-                    for i in 0..(*fields.borrow()).len() {
-                        let field_name = &(*fields.borrow())[i].0;
-                        if field_name == &name.lexeme {
-                            idx = i;
-                            found = true;
-                            break;
-                        }
-                    }
-
-                    if found {
-                        (*fields.borrow_mut())[idx].1 = value.clone();
-                    } else {
-                        (*fields.borrow_mut()).push((name.lexeme.clone(), value));
-                    }
+                    set_field(&fields, &name.lexeme, value);
 
                     Ok(Nil)
                 } else {
@@ -706,7 +1259,7 @@ impl Expr {
                         method.parent_env = method.parent_env.enclose();
                         method
                             .parent_env
-                            .define("this".to_string(), instance.clone());
+                            .define("this".to_string(), instance.clone())?;
                         Ok(Callable(LoxFunction(method)))
                     } else {
                         Err(format!(
@@ -744,53 +1297,7 @@ impl Expr {
             } => {
                 let left = left.evaluate(environment.clone())?;
                 let right = right.evaluate(environment.clone())?;
-
-                match (&left, operator.token_type, &right) {
-                    (Number(x), TokenType::Plus, Number(y)) => Ok(Number(x + y)),
-                    (Number(x), TokenType::Minus, Number(y)) => Ok(Number(x - y)),
-                    (Number(x), TokenType::Star, Number(y)) => Ok(Number(x * y)),
-                    (Number(x), TokenType::Slash, Number(y)) => Ok(Number(x / y)),
-                    (Number(x), TokenType::Greater, Number(y)) => {
-                        Ok(LiteralValue::from_bool(x > y))
-                    }
-                    (Number(x), TokenType::GreaterEqual, Number(y)) => {
-                        Ok(LiteralValue::from_bool(x >= y))
-                    }
-                    (Number(x), TokenType::Less, Number(y)) => Ok(LiteralValue::from_bool(x < y)),
-                    (Number(x), TokenType::LessEqual, Number(y)) => {
-                        Ok(LiteralValue::from_bool(x <= y))
-                    }
-
-                    (StringValue(_), op, Number(_)) => {
-                        Err(format!("{} is not defined for string and number", op))
-                    }
-                    (Number(_), op, StringValue(_)) => {
-                        Err(format!("{} is not defined for string and number", op))
-                    }
-
-                    (StringValue(s1), TokenType::Plus, StringValue(s2)) => {
-                        Ok(StringValue(format!("{}{}", s1, s2)))
-                    }
-
-                    (x, TokenType::BangEqual, y) => Ok(LiteralValue::from_bool(x != y)),
-                    (x, TokenType::EqualEqual, y) => Ok(LiteralValue::from_bool(x == y)),
-                    (StringValue(s1), TokenType::Greater, StringValue(s2)) => {
-                        Ok(LiteralValue::from_bool(s1 > s2))
-                    }
-                    (StringValue(s1), TokenType::GreaterEqual, StringValue(s2)) => {
-                        Ok(LiteralValue::from_bool(s1 >= s2))
-                    }
-                    (StringValue(s1), TokenType::Less, StringValue(s2)) => {
-                        Ok(LiteralValue::from_bool(s1 < s2))
-                    }
-                    (StringValue(s1), TokenType::LessEqual, StringValue(s2)) => {
-                        Ok(LiteralValue::from_bool(s1 <= s2))
-                    }
-                    (x, ttype, y) => Err(format!(
-                        "{} is not implemented for operands {:?} and {:?}",
-                        ttype, x, y
-                    )),
-                }
+                binary_op(&left, operator, &right)
             }
         }
     }
@@ -801,6 +1308,63 @@ impl Expr {
     }
 }
 
+// Errors name the operator's line, also when it comes from a compound
+// assignment like `x += 1`
+fn binary_op(
+    left: &LiteralValue,
+    operator: &Token,
+    right: &LiteralValue,
+) -> Result<LiteralValue, String> {
+    match (left, operator.token_type, right) {
+        (Number(x), TokenType::Plus, Number(y)) => Ok(Number(x + y)),
+        (Number(x), TokenType::Minus, Number(y)) => Ok(Number(x - y)),
+        (Number(x), TokenType::Star, Number(y)) => Ok(Number(x * y)),
+        (Number(_), TokenType::Slash | TokenType::Percent | TokenType::TildeSlash, Number(y))
+            if *y == 0.0 =>
+        {
+            Err(format!("Line {}: Division by zero", operator.line_number))
+        }
+        (Number(x), TokenType::Slash, Number(y)) => Ok(Number(x / y)),
+        (Number(x), TokenType::Percent, Number(y)) => Ok(Number(x % y)),
+        (Number(x), TokenType::TildeSlash, Number(y)) => Ok(Number((x / y).trunc())),
+        (Number(x), TokenType::StarStar, Number(y)) => Ok(Number(x.powf(*y))),
+        (Number(x), TokenType::Greater, Number(y)) => Ok(LiteralValue::from_bool(x > y)),
+        (Number(x), TokenType::GreaterEqual, Number(y)) => Ok(LiteralValue::from_bool(x >= y)),
+        (Number(x), TokenType::Less, Number(y)) => Ok(LiteralValue::from_bool(x < y)),
+        (Number(x), TokenType::LessEqual, Number(y)) => Ok(LiteralValue::from_bool(x <= y)),
+
+        (StringValue(_), op, Number(_)) => Err(format!(
+            "Line {}: {} is not defined for string and number",
+            operator.line_number, op
+        )),
+        (Number(_), op, StringValue(_)) => Err(format!(
+            "Line {}: {} is not defined for string and number",
+            operator.line_number, op
+        )),
+
+        (StringValue(s1), TokenType::Plus, StringValue(s2)) => {
+            Ok(StringValue(format!("{}{}", s1, s2)))
+        }
+
+        (x, TokenType::BangEqual, y) => Ok(LiteralValue::from_bool(x != y)),
+        (x, TokenType::EqualEqual, y) => Ok(LiteralValue::from_bool(x == y)),
+        (StringValue(s1), TokenType::Greater, StringValue(s2)) => {
+            Ok(LiteralValue::from_bool(s1 > s2))
+        }
+        (StringValue(s1), TokenType::GreaterEqual, StringValue(s2)) => {
+            Ok(LiteralValue::from_bool(s1 >= s2))
+        }
+        (StringValue(s1), TokenType::Less, StringValue(s2)) => Ok(LiteralValue::from_bool(s1 < s2)),
+        (StringValue(s1), TokenType::LessEqual, StringValue(s2)) => {
+            Ok(LiteralValue::from_bool(s1 <= s2))
+        }
+        (x, ttype, y) => Err(format!(
+            "Line {}: {} is not implemented for operands {:?} and {:?}",
+            operator.line_number, ttype, x, y
+        )),
+    }
+}
+
 pub fn run_lox_function(
     loxfun: LoxFunctionImpl,
     arguments: &Vec<Expr>,
@@ -823,10 +1387,17 @@ pub fn run_lox_function(
         arg_vals.push(val);
     }
 
+    call_lox_function(loxfun, arg_vals)
+}
+
+fn call_lox_function(
+    loxfun: LoxFunctionImpl,
+    arg_vals: Vec<LiteralValue>,
+) -> Result<LiteralValue, String> {
     let fun_env = loxfun.parent_env.enclose();
 
     for (i, val) in arg_vals.iter().enumerate() {
-        fun_env.define(loxfun.params[i].lexeme.clone(), (*val).clone());
+        fun_env.define(loxfun.params[i].lexeme.clone(), (*val).clone())?;
     }
 
     let mut int = Interpreter::with_env(fun_env);
@@ -843,6 +1414,106 @@ pub fn run_lox_function(
     Ok(LiteralValue::Nil)
 }
 
+fn list_index(
+    index: &LiteralValue,
+    len: usize,
+    kind: &str,
+    bracket: &Token,
+) -> Result<usize, String> {
+    match index {
+        Number(x) if x.fract() == 0.0 && *x >= 0.0 && (*x as usize) < len => Ok(*x as usize),
+        Number(x) => Err(format!(
+            "Line {}: Index {} out of bounds for {} of length {}",
+            bracket.line_number, x, kind, len
+        )),
+        other => Err(format!(
+            "Line {}: Index must be a Number, not {}",
+            bracket.line_number,
+            other.to_type()
+        )),
+    }
+}
+
+/// Calls a function value with already evaluated arguments, for natives
+/// that take a function such as `map` and `filter`.
+pub fn call_value(callee: &LiteralValue, args: Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    match callee {
+        Callable(callable) => {
+            let (name, arity) = match callable {
+                LoxFunction(loxfun) => (&loxfun.name, loxfun.arity),
+                NativeFunction(nativefun) => (&nativefun.name, nativefun.arity),
+            };
+            if args.len() != arity {
+                return Err(format!(
+                    "Callable {} expected {} arguments but got {}",
+                    name,
+                    arity,
+                    args.len()
+                ));
+            }
+
+            match callable {
+                LoxFunction(loxfun) => call_lox_function(loxfun.clone(), args),
+                NativeFunction(nativefun) => (nativefun.fun)(&args),
+            }
+        }
+        LoxClass { .. } => instantiate(callee, args),
+        other => Err(format!("{} is not callable", other.to_type())),
+    }
+}
+
+fn instantiate(class: &LiteralValue, args: Vec<LiteralValue>) -> Result<LiteralValue, String> {
+    let instance = LoxInstance {
+        class: Box::new(class.clone()),
+        fields: Rc::new(RefCell::new(vec![])),
+    };
+
+    // Call constructor if present
+    if let LoxClass { methods, .. } = class {
+        if let Some(init_method) = methods.get("init") {
+            if init_method.arity != args.len() {
+                return Err("Invalid number of arguments in constructor".to_string());
+            }
+
+            let mut init_method = init_method.clone();
+            init_method.parent_env = init_method.parent_env.enclose();
+            init_method
+                .parent_env
+                .define("this".to_string(), instance.clone())?;
+            call_lox_function(init_method, args)?;
+        }
+    }
+
+    Ok(instance)
+}
+
+fn get_field(fields: &RefCell<Vec<(String, LiteralValue)>>, name: &str) -> Option<LiteralValue> {
+    fields
+        .borrow()
+        .iter()
+        .find(|(field_name, _)| field_name == name)
+        .map(|(_, value)| value.clone())
+}
+
+fn set_field(fields: &RefCell<Vec<(String, LiteralValue)>>, name: &str, value: LiteralValue) {
+    let mut fields = fields.borrow_mut();
+    match fields.iter_mut().find(|(field_name, _)| field_name == name) {
+        Some(field) => field.1 = value,
+        None => fields.push((name.to_string(), value)),
+    }
+}
+
+fn map_key(index: &LiteralValue, bracket: &Token) -> Result<String, String> {
+    match index {
+        StringValue(key) => Ok(key.clone()),
+        other => Err(format!(
+            "Line {}: Map key must be a String, not {}",
+            bracket.line_number,
+            other.to_type()
+        )),
+    }
+}
+
 pub fn find_method(name: &str, class: LiteralValue) -> Option<LoxFunctionImpl> {
     if let LoxClass {
         name: _,
@@ -952,4 +1623,28 @@ mod tests {
             panic!("Failed");
         }
     }
+
+    #[test]
+    fn self_containing_lists_and_maps() {
+        let list = LiteralValue::from_list(vec![Number(1.0)]);
+        if let List(elements) = &list {
+            elements.borrow_mut().push(list.clone());
+        }
+        assert_eq!(list.to_string(), "[1, [...]]");
+        assert_eq!(format!("{:?}", list), "[1, [...]]");
+
+        let other = LiteralValue::from_list(vec![Number(1.0)]);
+        if let List(elements) = &other {
+            elements.borrow_mut().push(other.clone());
+        }
+        assert!(list == other);
+
+        let map = LiteralValue::from_map(vec![]);
+        if let Map(entries) = &map {
+            entries.borrow_mut().push(("self".to_string(), map.clone()));
+        }
+        assert_eq!(map.to_string(), r#"{"self": {...}}"#);
+        assert!(map == map.clone());
+        assert!(map != list);
+    }
 }
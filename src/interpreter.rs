@@ -1,14 +1,34 @@
 use crate::environment::Environment;
 use crate::expr::{CallableImpl, LiteralValue, LoxFunctionImpl, NativeFunctionImpl};
 use crate::scanner::Token;
+use crate::stdlib::process;
 use crate::stmt::Stmt;
 use std::collections::HashMap;
 use std::process::Command;
 use std::rc::Rc;
 
+fn native_value(
+    name: &str,
+    fun: impl Fn(&[LiteralValue]) -> Result<LiteralValue, String> + 'static,
+) -> LiteralValue {
+    LiteralValue::Callable(CallableImpl::NativeFunction(NativeFunctionImpl {
+        name: name.to_string(),
+        arity: 0,
+        fun: Rc::new(fun),
+    }))
+}
+
+pub struct TestResult {
+    pub name: String,
+    pub line: usize,
+    pub error: Option<String>,
+}
+
 pub struct Interpreter {
     pub specials: HashMap<String, LiteralValue>,
     pub environment: Environment,
+    // Test blocks are only run when this is set, otherwise they are skipped
+    pub test_results: Option<Vec<TestResult>>,
 }
 
 impl Interpreter {
@@ -16,9 +36,44 @@ impl Interpreter {
         Self {
             specials: HashMap::new(),
             environment: Environment::new(HashMap::new()),
+            test_results: None,
         }
     }
 
+    // Runs `test` blocks and collects their results instead of skipping them
+    pub fn for_tests() -> Self {
+        Self {
+            test_results: Some(vec![]),
+            ..Self::new()
+        }
+    }
+
+    // Makes the script path and the arguments given after it available to
+    // the script through `scriptPath()` and `args()`
+    pub fn set_script_args(&mut self, path: Option<&str>, args: &[String]) -> Result<(), String> {
+        let args = args.to_vec();
+        let args_fn = move |_args: &[LiteralValue]| {
+            let values = args
+                .iter()
+                .map(|arg| LiteralValue::StringValue(arg.clone()))
+                .collect();
+            Ok(LiteralValue::from_list(values))
+        };
+
+        let path = path.map(|path| path.to_string());
+        let path_fn = move |_args: &[LiteralValue]| match &path {
+            Some(path) => Ok(LiteralValue::StringValue(path.clone())),
+            None => Ok(LiteralValue::Nil),
+        };
+
+        self.environment
+            .define("args".to_string(), native_value("args", args_fn))?;
+        self.environment.define(
+            "scriptPath".to_string(),
+            native_value("scriptPath", path_fn),
+        )
+    }
+
     pub fn resolve(&mut self, locals: HashMap<usize, usize>) {
         self.environment.resolve(locals);
     }
@@ -27,6 +82,7 @@ impl Interpreter {
         Self {
             specials: HashMap::new(),
             environment: env,
+            test_results: None,
         }
     }
 
@@ -36,6 +92,7 @@ impl Interpreter {
         Self {
             specials: HashMap::new(),
             environment: env,
+            test_results: None,
         }
     }
 
@@ -49,9 +106,43 @@ impl Interpreter {
                     let value = expression.evaluate(self.environment.clone())?;
                     println!("{}", value.to_string());
                 }
-                Stmt::Var { name, initializer } => {
+                Stmt::Var {
+                    name,
+                    initializer,
+                    constant,
+                } => {
+                    let value = initializer.evaluate(self.environment.clone())?;
+                    if *constant {
+                        self.environment
+                            .define_constant(name.lexeme.clone(), value)?;
+                    } else {
+                        self.environment.define(name.lexeme.clone(), value)?;
+                    }
+                }
+                Stmt::Destructure {
+                    keyword,
+                    pattern,
+                    initializer,
+                    constant,
+                } => {
                     let value = initializer.evaluate(self.environment.clone())?;
-                    self.environment.define(name.lexeme.clone(), value);
+                    let mut bound = vec![];
+                    pattern.bind(&value, &mut bound).map_err(|msg| {
+                        format!(
+                            "Line {}: Cannot destructure {} as {}: {}",
+                            keyword.line_number,
+                            value.to_string(),
+                            pattern,
+                            msg
+                        )
+                    })?;
+                    for (name, value) in bound {
+                        if *constant {
+                            self.environment.define_constant(name, value)?;
+                        } else {
+                            self.environment.define(name, value)?;
+                        }
+                    }
                 }
                 Stmt::Block { statements } => {
                     let new_environment = self.environment.enclose();
@@ -90,11 +181,11 @@ impl Interpreter {
                     }
 
                     self.environment
-                        .define(name.lexeme.clone(), LiteralValue::Nil);
+                        .define(name.lexeme.clone(), LiteralValue::Nil)?;
 
                     self.environment = self.environment.enclose();
                     if let Some(sc) = superclass_value.clone() {
-                        self.environment.define("super".to_string(), *sc);
+                        self.environment.define("super".to_string(), *sc)?;
                     }
 
                     for method in methods {
@@ -119,7 +210,7 @@ impl Interpreter {
                         superclass: superclass_value,
                     };
 
-                    if !self.environment.assign_global(&name.lexeme, klass) {
+                    if !self.environment.assign_global(&name.lexeme, klass)? {
                         return Err(format!("Class definition failed for {}", name.lexeme));
                     }
 
@@ -154,28 +245,26 @@ impl Interpreter {
                 } => {
                     let callable = self.make_function(stmt);
                     let fun = LiteralValue::Callable(CallableImpl::LoxFunction(callable));
-                    self.environment.define(name.lexeme.clone(), fun);
+                    self.environment.define(name.lexeme.clone(), fun)?;
                 }
                 Stmt::CmdFunction { name, cmd } => {
                     // Return a callable that runs a shell command, captures the stdout and returns
                     // it in a String
 
                     let cmd = cmd.clone();
-                    let local_fn = move |_args: &Vec<LiteralValue>| {
-                        let cmd = cmd.clone();
-                        let parts = cmd.split(" ").collect::<Vec<&str>>();
-                        let mut command = Command::new(parts[0].replace("\"", ""));
-                        for part in parts[1..].iter() {
-                            command.arg(part.replace("\"", ""));
-                        }
-                        let output = command.output().expect("Failed to run command");
-
+                    let local_fn = move |_args: &[LiteralValue]| {
+                        let words = process::split_command(&cmd)?;
+                        let mut command = Command::new(&words[0]);
+                        command.args(&words[1..]);
+                        let output = command
+                            .output()
+                            .map_err(|e| format!("Failed to run command {}: {}", words[0], e))?;
 
-                        return LiteralValue::StringValue(
+                        Ok(LiteralValue::StringValue(
                             std::str::from_utf8(output.stdout.as_slice())
                                 .unwrap()
                                 .to_string(),
-                        );
+                        ))
                     };
 
                     let fun_val =
@@ -184,7 +273,7 @@ impl Interpreter {
                             arity: 0,
                             fun: Rc::new(local_fn),
                         }));
-                    self.environment.define(name.lexeme.clone(), fun_val);
+                    self.environment.define(name.lexeme.clone(), fun_val)?;
                 }
                 Stmt::ReturnStmt { keyword: _, value } => {
                     let eval_val;
@@ -195,6 +284,28 @@ impl Interpreter {
                     }
                     self.specials.insert("return".to_string(), eval_val);
                 }
+                Stmt::Test { name, body } => {
+                    if self.test_results.is_none() {
+                        continue;
+                    }
+
+                    let old_environment = self.environment.clone();
+                    self.environment = self.environment.enclose();
+                    let test_result = self.interpret(body.iter().collect());
+                    self.environment = old_environment;
+                    if process::exit_requested() {
+                        return test_result;
+                    }
+
+                    let result = TestResult {
+                        name: LiteralValue::from_token(name.clone()).to_string(),
+                        line: name.line_number,
+                        error: test_result.err(),
+                    };
+                    if let Some(results) = self.test_results.as_mut() {
+                        results.push(result);
+                    }
+                }
             };
         }
 
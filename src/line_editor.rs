@@ -0,0 +1,387 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use unicode_width::UnicodeWidthChar;
+
+const HISTORY_FILE: &str = ".dena_history";
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, PartialEq)]
+pub enum Input {
+    Line(String),
+    Interrupted,
+    Eof,
+}
+
+// Puts the terminal into a character-at-a-time, no-echo mode for as long as
+// it is alive. Uses `stty` so that no terminal library is needed.
+struct RawMode {
+    saved: String,
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout).ok()
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        let saved = stty(&["-g"])?.trim().to_string();
+        stty(&[
+            "-icanon", "-echo", "-isig", "-ixon", "min", "1", "time", "0",
+        ])?;
+
+        Some(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+// Reads the rest of a UTF-8 encoded char whose first byte is `first`
+fn read_char(bytes: &mut impl Iterator<Item = u8>, first: u8) -> Option<char> {
+    let len = match first {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        _ => 4,
+    };
+
+    let mut buffer = vec![first];
+    for _ in 1..len {
+        buffer.push(bytes.next()?);
+    }
+
+    String::from_utf8(buffer)
+        .ok()
+        .and_then(|s| s.chars().next())
+}
+
+// The line being edited, apart from the terminal so that the key handling
+// works on any bytes
+struct EditState<'a> {
+    history: &'a [String],
+    line: Vec<char>,
+    cursor: usize,
+    history_idx: usize,
+    // The line that was being typed before browsing the history
+    pending: Vec<char>,
+}
+
+impl<'a> EditState<'a> {
+    fn new(history: &'a [String]) -> Self {
+        Self {
+            history,
+            line: vec![],
+            cursor: 0,
+            history_idx: history.len(),
+            pending: vec![],
+        }
+    }
+
+    // Handles the key that starts with the next byte, the input is returned
+    // once the line is finished
+    fn handle_key(&mut self, bytes: &mut impl Iterator<Item = u8>) -> Option<Input> {
+        let byte = match bytes.next() {
+            Some(byte) => byte,
+            None if self.line.is_empty() => return Some(Input::Eof),
+            None => b'\n',
+        };
+
+        match byte {
+            b'\r' | b'\n' => return Some(Input::Line(self.line.iter().collect())),
+            // Ctrl-C
+            3 => return Some(Input::Interrupted),
+            // Ctrl-D
+            4 if self.line.is_empty() => return Some(Input::Eof),
+            4 if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            // Backspace
+            8 | 127 if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            127 => (),
+            // Ctrl-A and Ctrl-E
+            1 => self.cursor = 0,
+            5 => self.cursor = self.line.len(),
+            // Escape sequences for the cursor, home, end and delete keys
+            0x1b => {
+                let kind = bytes.next();
+                if kind != Some(b'[') && kind != Some(b'O') {
+                    return None;
+                }
+
+                let mut code = bytes.next().unwrap_or(0);
+                let mut number = String::new();
+                while code.is_ascii_digit() {
+                    number.push(code as char);
+                    code = bytes.next().unwrap_or(0);
+                }
+
+                match (code, number.as_str()) {
+                    (b'A', _) if self.history_idx > 0 => {
+                        if self.history_idx == self.history.len() {
+                            self.pending = self.line.clone();
+                        }
+                        self.history_idx -= 1;
+                        self.line = self.history[self.history_idx].chars().collect();
+                        self.cursor = self.line.len();
+                    }
+                    (b'B', _) if self.history_idx < self.history.len() => {
+                        self.history_idx += 1;
+                        self.line = if self.history_idx == self.history.len() {
+                            self.pending.clone()
+                        } else {
+                            self.history[self.history_idx].chars().collect()
+                        };
+                        self.cursor = self.line.len();
+                    }
+                    (b'C', _) => self.cursor = (self.cursor + 1).min(self.line.len()),
+                    (b'D', _) => self.cursor = self.cursor.saturating_sub(1),
+                    (b'H', _) | (b'~', "1") | (b'~', "7") => self.cursor = 0,
+                    (b'F', _) | (b'~', "4") | (b'~', "8") => self.cursor = self.line.len(),
+                    (b'~', "3") if self.cursor < self.line.len() => {
+                        self.line.remove(self.cursor);
+                    }
+                    _ => (),
+                }
+            }
+            byte if byte >= 0x20 => {
+                if let Some(ch) = read_char(bytes, byte) {
+                    self.line.insert(self.cursor, ch);
+                    self.cursor += 1;
+                }
+            }
+            _ => (),
+        }
+
+        None
+    }
+
+    // Rewrites the whole line and moves the cursor back to its place
+    fn redraw(&self, prompt: &str) -> String {
+        let text = self.line.iter().collect::<String>();
+        let mut output = format!("\r{prompt}{text}\x1b[K");
+        // Wide chars like CJK take two columns, combining marks none
+        let columns = self.line[self.cursor..]
+            .iter()
+            .map(|ch| ch.width().unwrap_or(0))
+            .sum::<usize>();
+        if columns > 0 {
+            output.push_str(&format!("\x1b[{columns}D"));
+        }
+
+        output
+    }
+}
+
+// Edits a line from the key presses in `bytes`, writing the echo to `out`
+fn edit(
+    history: &[String],
+    prompt: &str,
+    mut bytes: impl Iterator<Item = u8>,
+    out: &mut impl Write,
+) -> Input {
+    let mut state = EditState::new(history);
+    loop {
+        if let Some(input) = state.handle_key(&mut bytes) {
+            let _ = match input {
+                Input::Line(_) => writeln!(out),
+                Input::Interrupted => writeln!(out, "^C"),
+                Input::Eof => Ok(()),
+            };
+            return input;
+        }
+
+        let _ = write!(out, "{}", state.redraw(prompt));
+        let _ = out.flush();
+    }
+}
+
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+    interactive: bool,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+        let history_path = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+
+        let mut history = vec![];
+        if let (true, Some(path)) = (interactive, &history_path) {
+            if let Ok(contents) = fs::read_to_string(path) {
+                history = contents.lines().map(|line| line.to_string()).collect();
+                let skip = history.len().saturating_sub(MAX_HISTORY);
+                history.drain(..skip);
+            }
+        }
+
+        Self {
+            history,
+            history_path,
+            interactive,
+        }
+    }
+
+    /// Reads one line without its line ending. On a terminal the line can be
+    /// edited with the cursor keys and the history browsed with up and down.
+    pub fn read_line(&mut self, prompt: &str) -> Result<Input, String> {
+        print!("{prompt}");
+        if io::stdout().flush().is_err() {
+            return Err("Could not flush stdout".to_string());
+        }
+
+        if self.interactive {
+            if let Some(_raw_mode) = RawMode::enable() {
+                return self.read_edited(prompt);
+            }
+        }
+
+        let mut buffer = String::new();
+        match io::stdin().lock().read_line(&mut buffer) {
+            Ok(0) => Ok(Input::Eof),
+            Ok(_) => Ok(Input::Line(
+                buffer.trim_end_matches(['\n', '\r']).to_string(),
+            )),
+            Err(_) => Err("Couldnt read line".to_string()),
+        }
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+
+        if !self.interactive {
+            return;
+        }
+        if let Some(path) = &self.history_path {
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            if let Ok(mut file) = file {
+                let _ = writeln!(file, "{line}");
+            }
+        }
+    }
+
+    fn read_edited(&mut self, prompt: &str) -> Result<Input, String> {
+        let mut error = None;
+        let bytes = io::stdin()
+            .lock()
+            .bytes()
+            .map_while(|byte| byte.map_err(|e| error = Some(e)).ok());
+        let input = edit(&self.history, prompt, bytes, &mut io::stdout());
+
+        match error {
+            Some(_) => Err("Couldnt read line".to_string()),
+            None => Ok(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The input and the echo of editing a line with `history`
+    fn edit_keys(history: &[&str], keys: &str) -> (Input, String) {
+        let history = history
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        let mut out = vec![];
+        let input = edit(&history, "> ", keys.bytes(), &mut out);
+        (input, String::from_utf8(out).unwrap())
+    }
+
+    fn line(text: &str) -> Input {
+        Input::Line(text.to_string())
+    }
+
+    #[test]
+    fn insert() {
+        assert_eq!(edit_keys(&[], "ac\x1b[Db\r").0, line("abc"));
+        assert_eq!(edit_keys(&[], "hé中\n").0, line("hé中"));
+        assert_eq!(edit_keys(&[], "ab").0, line("ab"));
+    }
+
+    #[test]
+    fn backspace_and_delete() {
+        assert_eq!(edit_keys(&[], "abc\x7f\x08d\r").0, line("ad"));
+        assert_eq!(edit_keys(&[], "\x7fa\x1b[D\x7f\r").0, line("a"));
+        assert_eq!(edit_keys(&[], "abc\x1b[H\x1b[3~\x04\r").0, line("c"));
+    }
+
+    #[test]
+    fn home_and_end() {
+        assert_eq!(edit_keys(&[], "bc\x1b[Ha\x1b[Fd\r").0, line("abcd"));
+        assert_eq!(edit_keys(&[], "bc\x01a\x05d\r").0, line("abcd"));
+        assert_eq!(edit_keys(&[], "bc\x1b[1~a\x1b[4~d\r").0, line("abcd"));
+        assert_eq!(edit_keys(&[], "bc\x1bOHa\x1bOFd\r").0, line("abcd"));
+    }
+
+    #[test]
+    fn history_up_and_down() {
+        let history = ["first", "second"];
+        assert_eq!(edit_keys(&history, "\x1b[A\r").0, line("second"));
+        assert_eq!(edit_keys(&history, "\x1b[A\x1b[A\x1b[A\r").0, line("first"));
+        assert_eq!(
+            edit_keys(&history, "\x1b[A\x1b[A\x1b[B!\r").0,
+            line("second!")
+        );
+        assert_eq!(edit_keys(&history, "new\x1b[A\x1b[B\r").0, line("new"));
+        assert_eq!(edit_keys(&[], "\x1b[A\x1b[Bx\r").0, line("x"));
+    }
+
+    #[test]
+    fn interrupt_and_eof() {
+        assert_eq!(
+            edit_keys(&[], "ab\x03"),
+            (
+                Input::Interrupted,
+                "\r> a\x1b[K\r> ab\x1b[K^C\n".to_string()
+            )
+        );
+        assert_eq!(edit_keys(&[], "").0, Input::Eof);
+        assert_eq!(edit_keys(&[], "\x04").0, Input::Eof);
+        assert_eq!(edit_keys(&[], "ab\x1b[D\x04\x04\r").0, line("a"));
+    }
+
+    #[test]
+    fn redraw_wide_chars() {
+        let (_, out) = edit_keys(&[], "a中b\x1b[D\x1b[D\r");
+        let redraws = out.split('\r').skip(1).collect::<Vec<_>>();
+        assert_eq!(
+            redraws,
+            [
+                "> a\x1b[K",
+                "> a中\x1b[K",
+                "> a中b\x1b[K",
+                "> a中b\x1b[K\x1b[1D",
+                "> a中b\x1b[K\x1b[3D\n",
+            ]
+        );
+
+        // Combining marks take no columns
+        let (_, out) = edit_keys(&[], "e\u{301}x\x1b[D\x1b[D\r");
+        assert!(out.ends_with("\r> e\u{301}x\x1b[K\x1b[1D\n"));
+    }
+}
@@ -1,30 +1,55 @@
 mod environment;
 mod expr;
 mod interpreter;
+mod line_editor;
 mod parser;
+mod pattern;
+mod repl;
 mod resolver;
 mod scanner;
+mod stdlib;
 mod stmt;
+mod test_runner;
 mod tests;
 use crate::interpreter::*;
 use crate::parser::*;
+use crate::repl::run_prompt;
 use crate::resolver::*;
 use crate::scanner::*;
+use crate::stdlib::process::take_exit_code;
+use crate::test_runner::{run_tests, run_with_test_blocks};
 use std::env;
 use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, Read};
 use std::process::exit;
 
-pub fn run_file(path: &str) -> Result<(), String> {
-    // let mut interpreter = Interpreter::new();
-    match fs::read_to_string(path) {
-        Err(msg) => return Err(msg.to_string()),
-        Ok(contents) => return run_string(&contents),
+// Reads a script from a file, or from stdin if the path is "-"
+fn read_script(path: &str) -> Result<String, String> {
+    if path == "-" {
+        let mut contents = String::new();
+        return match io::stdin().read_to_string(&mut contents) {
+            Ok(_) => Ok(contents),
+            Err(msg) => Err(msg.to_string()),
+        };
+    }
+
+    fs::read_to_string(path).map_err(|msg| msg.to_string())
+}
+
+pub fn run_file(path: &str, args: &[String]) -> Result<(), String> {
+    match read_script(path) {
+        Err(msg) => Err(msg),
+        Ok(contents) => {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_script_args(Some(path), args)?;
+            run(&mut interpreter, &contents)
+        }
     }
 }
 
-pub fn run_string(contents: &str) -> Result<(), String> {
+pub fn run_string(contents: &str, args: &[String]) -> Result<(), String> {
     let mut interpreter = Interpreter::new();
+    interpreter.set_script_args(None, args)?;
 
     run(&mut interpreter, contents)
 }
@@ -45,67 +70,124 @@ fn run(interpreter: &mut Interpreter, contents: &str) -> Result<(), String> {
     return Ok(());
 }
 
-fn run_prompt() -> Result<(), String> {
-    let mut interpreter = Interpreter::new();
-    loop {
-        print!("> ");
-        match io::stdout().flush() {
-            Ok(_) => (),
-            Err(_) => return Err("Could not flush stdout".to_string()),
-        }
+// Prints the output of one of the interpreter stages for debugging scripts
+fn dump_file(stage: &str, path: &str) -> Result<(), String> {
+    let contents = read_script(path)?;
 
-        let mut buffer = String::new();
-        let stdin = io::stdin();
-        let mut handle = stdin.lock();
-        match handle.read_line(&mut buffer) {
-            Ok(n) => {
-                if n == 0 {
-                    println!("");
-                    return Ok(());
-                } else if n == 1 {
-                    continue;
-                }
-            }
-            Err(_) => return Err("Couldnt read line".to_string()),
+    let mut scanner = Scanner::new(&contents);
+    let tokens = scanner.scan_tokens()?;
+    if stage == "--tokens" {
+        for token in tokens {
+            println!("{:>4} {}", token.line_number, token.to_string());
         }
+        return Ok(());
+    }
 
-        println!("ECHO: {}", buffer);
-        match run(&mut interpreter, &buffer) {
-            Ok(_) => (),
-            Err(msg) => println!("{}", msg),
+    let mut parser = Parser::new(tokens);
+    let stmts = parser.parse()?;
+    if stage == "--ast" {
+        for stmt in stmts {
+            println!("{}", stmt.to_string());
         }
+        return Ok(());
+    }
+
+    let resolver = Resolver::new();
+    for resolution in resolver.resolutions(&stmts.iter().collect())? {
+        let depth = match resolution.depth {
+            Some(depth) => depth.to_string(),
+            None => "global".to_string(),
+        };
+        println!(
+            "{:>4} line {:<4} {:<20} {}",
+            resolution.id, resolution.name.line_number, resolution.name.lexeme, depth
+        );
     }
+
+    Ok(())
 }
 
+const USAGE: &str = "\
+Usage: dena [--tokens | --ast | --resolve] [--] [script|- [args...]]
+       dena e <code> [args...]
+       dena test [--bless] [paths...]";
+
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() == 2 {
-        match run_file(&args[1]) {
-            Ok(_) => exit(0),
+    if args.len() >= 2 && args[1] == "test" {
+        let bless = args[2..].iter().any(|arg| arg == "--bless");
+        let paths: Vec<String> = args[2..]
+            .iter()
+            .filter(|arg| *arg != "--bless")
+            .cloned()
+            .collect();
+        match run_tests(&paths, bless) {
+            Ok(true) => exit(0),
+            Ok(false) => exit(1),
             Err(msg) => {
-                println!("ERROR:\n{}", msg);
+                println!("ERROR:\n{msg}");
                 exit(1);
             }
         }
-    } else if args.len() == 3 && args[1] == "e" {
-        match run_string(&args[2]) {
-            Ok(_) => exit(0),
-            Err(msg) => {
-                println!("ERROR:\n{msg}");
-                exit(1);
+    }
+
+    // Interpreter flags come before the script, everything after the script
+    // is passed on to it. `--` ends the interpreter flags.
+    let mut stage = None;
+    // Not in the usage, `dena test` runs its cases with it
+    let mut test_blocks = false;
+    let mut separated = false;
+    let mut idx = 1;
+    while idx < args.len() && args[idx].starts_with("--") {
+        idx += 1;
+        match args[idx - 1].as_str() {
+            "--" => {
+                separated = true;
+                break;
+            }
+            "--tokens" | "--ast" | "--resolve" => stage = Some(args[idx - 1].clone()),
+            "--test-blocks" => test_blocks = true,
+            flag => {
+                println!("Unknown flag {flag}\n{USAGE}");
+                exit(64);
             }
         }
-    } else if args.len() == 1 {
-        match run_prompt() {
-            Ok(_) => exit(0),
-            Err(msg) => {
-                println!("ERROR\n{}", msg);
-                exit(1);
+    }
+    let rest = &args[idx..];
+
+    let result = match (rest.first(), stage) {
+        (None, None) => run_prompt(),
+        (None, Some(_)) => {
+            println!("{USAGE}");
+            exit(64);
+        }
+        (Some(_), Some(stage)) => dump_file(&stage, &rest[0]),
+        (Some(cmd), None) if cmd == "e" && !separated => {
+            if rest.len() < 2 {
+                println!("{USAGE}");
+                exit(64);
+            }
+            if test_blocks {
+                run_with_test_blocks(&rest[1])
+            } else {
+                run_string(&rest[1], &rest[2..])
             }
         }
-    } else {
-        println!("Usage: dena [script]");
-        exit(64);
+        (Some(path), None) => run_file(path, &rest[1..]),
+    };
+
+    // The script called exit(code), which unwound the interpreter
+    if let Some(code) = take_exit_code() {
+        exit(code);
+    }
+
+    match result {
+        Ok(_) => exit(0),
+        Err(msg) => {
+            // Scan errors come one per line, each with its line break
+            println!("ERROR:\n{}", msg.trim_end());
+            exit(1);
+        }
     }
 }
@@ -1,4 +1,5 @@
-use crate::expr::{Expr, Expr::*, LiteralValue};
+use crate::expr::{Expr, Expr::*, LiteralValue, MatchArm};
+use crate::pattern::Pattern;
 use crate::scanner::{Token, TokenType, TokenType::*};
 use crate::stmt::Stmt;
 
@@ -6,6 +7,8 @@ pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     next_id: usize,
+    // The '=>' ending the match guard being parsed
+    guard_end: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -14,15 +17,36 @@ enum FunctionKind {
     Method,
 }
 
+// An argument `_` of a piped call takes the piped value
+fn is_placeholder(expr: &Expr) -> bool {
+    matches!(expr, Variable { id: _, name } if name.lexeme == "_")
+}
+
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
         Self {
             tokens,
             current: 0,
             next_id: 0,
+            guard_end: None,
+        }
+    }
+
+    // Continues the expression id space of an earlier parser, so that the
+    // ids from several inputs (e.g. REPL lines) never collide
+    pub fn starting_at(tokens: Vec<Token>, next_id: usize) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            next_id,
+            guard_end: None,
         }
     }
 
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
+
     fn get_id(&mut self) -> usize {
         let id = self.next_id;
         self.next_id += 1;
@@ -53,12 +77,14 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> Result<Stmt, String> {
-        if self.match_token(Var) {
+        if self.match_token(Var) || self.match_token(Const) {
             self.var_declaration()
         } else if self.match_token(Fun) {
             self.function(FunctionKind::Function)
         } else if self.match_token(Class) {
             self.class_declaration()
+        } else if self.check_test_block() {
+            self.test_declaration()
         } else {
             self.statement()
         }
@@ -93,44 +119,51 @@ impl Parser {
         })
     }
 
+    // 'test' is only a keyword when followed by a string, so it can still be used as a name
+    fn check_test_block(&mut self) -> bool {
+        let token = self.peek();
+        token.token_type == Identifier
+            && token.lexeme == "test"
+            && self.tokens[self.current + 1].token_type == StringLit
+    }
+
+    fn test_declaration(&mut self) -> Result<Stmt, String> {
+        self.advance();
+        let name = self.consume(StringLit, "Expected test name after 'test'.")?;
+        self.consume(LeftBrace, "Expected '{' before test body.")?;
+        let body = match self.block_statement()? {
+            Stmt::Block { statements } => statements.into_iter().map(|stmt| *stmt).collect(),
+            _ => panic!("Block statement parsed something that was not a block"),
+        };
+
+        Ok(Stmt::Test { name, body })
+    }
+
     fn function(&mut self, kind: FunctionKind) -> Result<Stmt, String> {
         let name = self.consume(Identifier, &format!("Expected {kind:?} name"))?;
 
         if self.match_token(Gets) {
-            let cmd_body = self.consume(StringLit, "Expected command body")?; 
+            let cmd_body = self.consume(StringLit, "Expected command body")?;
             self.consume(Semicolon, "Expected ';' after command body")?;
 
-            return Ok(Stmt::CmdFunction {
-                name,
-                cmd: cmd_body.lexeme,
-            });
+            let cmd = match LiteralValue::from_token(cmd_body) {
+                LiteralValue::StringValue(cmd) => cmd,
+                _ => unreachable!(),
+            };
+            return Ok(Stmt::CmdFunction { name, cmd });
         }
 
         self.consume(LeftParen, &format!("Expected '(' after {kind:?} name"))?;
-
-        let mut parameters = vec![];
-        if !self.check(RightParen) {
-            loop {
-                if parameters.len() >= 255 {
-                    let location = self.peek().line_number;
-                    return Err(format!(
-                        "Line {location}: Cant have more than 255 arguments"
-                    ));
-                }
-
-                let param = self.consume(Identifier, "Expected parameter name")?;
-                parameters.push(param);
-
-                if !self.match_token(Comma) {
-                    break;
-                }
-            }
-        }
+        let (parameters, destructuring) = self.parameters()?;
+        let mut body = destructuring
+            .into_iter()
+            .map(Box::new)
+            .collect::<Vec<Box<Stmt>>>();
         self.consume(RightParen, "Expected ')' after parameters.")?;
 
         self.consume(LeftBrace, &format!("Expected '{{' before {kind:?} body."))?;
-        let body = match self.block_statement()? {
-            Stmt::Block { statements } => statements,
+        match self.block_statement()? {
+            Stmt::Block { statements } => body.extend(statements),
             _ => panic!("Block statement parsed something that was not a block"),
         };
 
@@ -141,12 +174,34 @@ impl Parser {
         })
     }
 
+    // `var` or `const`, whose token has already been consumed
     fn var_declaration(&mut self) -> Result<Stmt, String> {
+        let constant = self.previous().token_type == Const;
+        if self.destructuring_ahead() {
+            let keyword = self.previous();
+            let pattern = self.pattern()?;
+            self.consume(Equal, "Expected '=' after destructuring pattern")?;
+            let initializer = self.expression()?;
+            self.consume(Semicolon, "Expected ';' after variable declaration")?;
+
+            return Ok(Stmt::Destructure {
+                keyword,
+                pattern,
+                initializer,
+                constant,
+            });
+        }
+
         let token = self.consume(Identifier, "Expected variable name")?;
 
         let initializer;
         if self.match_token(Equal) {
             initializer = self.expression()?;
+        } else if constant {
+            return Err(format!(
+                "Line {}: Expected '=' after constant name",
+                token.line_number
+            ));
         } else {
             initializer = Literal {
                 id: self.get_id(),
@@ -159,6 +214,7 @@ impl Parser {
         Ok(Stmt::Var {
             name: token,
             initializer,
+            constant,
         })
     }
 
@@ -325,9 +381,24 @@ impl Parser {
         self.assignment()
     }
 
-    fn function_expression(&mut self) -> Result<Expr, String> {
-        let paren = self.consume(LeftParen, "Expected '(' after anonymous function")?;
+    // `[a, b]`, `{name, age}` and `Point{x, y}` destructure, a name alone
+    // does not
+    fn destructuring_ahead(&self) -> bool {
+        match self.tokens[self.current].token_type {
+            LeftBracket | LeftBrace => true,
+            Identifier => {
+                self.tokens.get(self.current + 1).map(|t| t.token_type) == Some(LeftBrace)
+            }
+            _ => false,
+        }
+    }
+
+    // Comma separated parameters, up to the closing ')'. A pattern like
+    // `[a, b]` becomes a hidden parameter, which the returned statements
+    // destructure at the start of the function body.
+    fn parameters(&mut self) -> Result<(Vec<Token>, Vec<Stmt>), String> {
         let mut parameters = vec![];
+        let mut destructuring = vec![];
         if !self.check(RightParen) {
             loop {
                 if parameters.len() >= 255 {
@@ -337,14 +408,102 @@ impl Parser {
                     ));
                 }
 
-                let param = self.consume(Identifier, "Expected parameter name")?;
-                parameters.push(param);
+                if self.destructuring_ahead() {
+                    let keyword = self.peek();
+                    let pattern = self.pattern()?;
+                    let param = Token {
+                        token_type: Identifier,
+                        lexeme: format!("<parameter {}>", parameters.len() + 1),
+                        literal: None,
+                        line_number: keyword.line_number,
+                    };
+                    destructuring.push(Stmt::Destructure {
+                        keyword,
+                        pattern,
+                        initializer: Variable {
+                            id: self.get_id(),
+                            name: param.clone(),
+                        },
+                        constant: false,
+                    });
+                    parameters.push(param);
+                } else {
+                    let param = self.consume(Identifier, "Expected parameter name")?;
+                    parameters.push(param);
+                }
 
                 if !self.match_token(Comma) {
                     break;
                 }
             }
         }
+
+        Ok((parameters, destructuring))
+    }
+
+    // `x =>` or `(a, b) =>` starts an arrow function
+    fn arrow_ahead(&self) -> bool {
+        let token_type = |idx: usize| self.tokens.get(idx).map(|token| token.token_type);
+        let mut idx = self.current + 1;
+        if token_type(self.current) == Some(LeftParen) {
+            // Parameters are names and patterns, which are made of these tokens
+            let mut depth = 0;
+            loop {
+                match token_type(idx) {
+                    Some(LeftBracket | LeftBrace) => depth += 1,
+                    Some(RightBracket | RightBrace) if depth > 0 => depth -= 1,
+                    Some(RightParen) if depth == 0 => break,
+                    Some(
+                        Identifier | Comma | Colon | Bar | Minus | Number | StringLit | True
+                        | False | Nil,
+                    ) => (),
+                    _ => return false,
+                }
+                idx += 1;
+            }
+            idx += 1;
+        }
+
+        token_type(idx) == Some(Arrow) && self.guard_end != Some(idx)
+    }
+
+    // `(a, b) => a + b` is `fun (a, b) { return a + b; }`. The body extends
+    // as far as possible, so `x => x |> f` pipes inside the body.
+    fn arrow_function(&mut self) -> Result<Expr, String> {
+        let paren = self.advance();
+        let (parameters, destructuring) = if paren.token_type == LeftParen {
+            let parameters = self.parameters()?;
+            self.consume(RightParen, "Expected ')' after arrow function parameters")?;
+            parameters
+        } else {
+            (vec![paren.clone()], vec![])
+        };
+        let mut body = destructuring
+            .into_iter()
+            .map(Box::new)
+            .collect::<Vec<Box<Stmt>>>();
+        let arrow = self.consume(Arrow, "Expected '=>' after arrow function parameters")?;
+        let value = self.expression()?;
+        body.push(Box::new(Stmt::ReturnStmt {
+            keyword: arrow,
+            value: Some(value),
+        }));
+
+        Ok(Expr::AnonFunction {
+            id: self.get_id(),
+            paren,
+            arguments: parameters,
+            body,
+        })
+    }
+
+    fn function_expression(&mut self) -> Result<Expr, String> {
+        let paren = self.consume(LeftParen, "Expected '(' after anonymous function")?;
+        let (parameters, destructuring) = self.parameters()?;
+        let mut body = destructuring
+            .into_iter()
+            .map(Box::new)
+            .collect::<Vec<Box<Stmt>>>();
         self.consume(
             RightParen,
             "Expected ')' after anonymous function parameters",
@@ -355,8 +514,8 @@ impl Parser {
             "Expected '{' after anonymous function declaration",
         )?;
 
-        let body = match self.block_statement()? {
-            Stmt::Block { statements } => statements,
+        match self.block_statement()? {
+            Stmt::Block { statements } => body.extend(statements),
             _ => panic!("Block statement parsed something that was not a block"),
         };
 
@@ -370,7 +529,7 @@ impl Parser {
 
     fn assignment(&mut self) -> Result<Expr, String> {
         // a = 2; NOT var a = 2;
-        let expr = self.pipe()?; // a |> f = 2;
+        let expr = self.conditional()?; // a |> f = 2;
 
         if self.match_token(Equal) {
             let value = self.expression()?;
@@ -391,13 +550,123 @@ impl Parser {
                     name,
                     value: Box::new(value),
                 }),
+                Index {
+                    id: _,
+                    object,
+                    bracket,
+                    index,
+                } => Ok(SetIndex {
+                    id: self.get_id(),
+                    object,
+                    bracket,
+                    index,
+                    value: Box::new(value),
+                }),
                 _ => Err("Invalid assignment target.".to_string()),
             }
+        } else if self.match_tokens(&[PlusEqual, MinusEqual, StarEqual, SlashEqual, PercentEqual]) {
+            let operator = self.previous();
+            let value = self.expression()?;
+            self.compound_assignment(expr, operator, value, false)
         } else {
             Ok(expr)
         }
     }
 
+    // `target += value` and `++target` update the target in place, and
+    // `target++` does too but gives the old value. The object and index of
+    // the target are evaluated once. This is not desugared into `target =
+    // target + value`, because that evaluates the object and index twice,
+    // and evaluating them once would need a temporary, which the grammar can
+    // only get from a function call. A call would also hide the operator's
+    // line from errors and move any `this` in the value into a nested function
+    fn compound_assignment(
+        &mut self,
+        target: Expr,
+        operator: Token,
+        value: Expr,
+        postfix: bool,
+    ) -> Result<Expr, String> {
+        let operator = Token {
+            token_type: match operator.token_type {
+                PlusEqual | PlusPlus => Plus,
+                MinusEqual | MinusMinus => Minus,
+                StarEqual => Star,
+                SlashEqual => Slash,
+                _ => Percent,
+            },
+            lexeme: operator.lexeme[..1].to_string(),
+            literal: None,
+            line_number: operator.line_number,
+        };
+        let value = Box::new(value);
+
+        match target {
+            Variable { id: _, name } => Ok(CompoundAssign {
+                id: self.get_id(),
+                name,
+                operator,
+                value,
+                postfix,
+            }),
+            Get {
+                id: _,
+                object,
+                name,
+            } => Ok(CompoundSet {
+                id: self.get_id(),
+                object,
+                name,
+                operator,
+                value,
+                postfix,
+            }),
+            Index {
+                id: _,
+                object,
+                bracket,
+                index,
+            } => Ok(CompoundIndexSet {
+                id: self.get_id(),
+                object,
+                bracket,
+                index,
+                operator,
+                value,
+                postfix,
+            }),
+            _ => Err("Invalid assignment target.".to_string()),
+        }
+    }
+
+    // `++x` and `x++` add 1 like `x += 1`
+    fn increment(&mut self, target: Expr, operator: Token, postfix: bool) -> Result<Expr, String> {
+        let one = Literal {
+            id: self.get_id(),
+            value: LiteralValue::Number(1.0),
+        };
+        self.compound_assignment(target, operator, one, postfix)
+    }
+
+    // a ? b : c ? d : e is a ? b : (c ? d : e)
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let condition = self.pipe()?;
+        if !self.match_token(Question) {
+            return Ok(condition);
+        }
+
+        let then_branch = self.expression()?;
+        self.consume(Colon, "Expected ':' after the '?' branch of a conditional.")?;
+        let else_branch = self.conditional()?;
+
+        Ok(Conditional {
+            id: self.get_id(),
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+
     fn pipe(&mut self) -> Result<Expr, String> {
         // expr |> f
         // expr |> f1 |> f2
@@ -406,17 +675,60 @@ impl Parser {
         // (expr |> f1) |> f2
 
         // expr |> fun (a) { return a + 1; }
-        // expr |> a -> a + 1
+        // expr |> a => a + 1
+
+        // expr |> f(_, 10) -> f(expr, 10)
+        // expr |> .map(f) -> expr.map(f)
         let mut expr = self.or()?;
         while self.match_token(Pipe) {
             let pipe = self.previous();
+            if self.match_token(Dot) {
+                let name = self.consume(Identifier, "Expected method name after '|> .'")?;
+                let method = Get {
+                    id: self.get_id(),
+                    object: Box::new(expr),
+                    name,
+                };
+                expr = self.call_suffixes(method)?;
+                continue;
+            }
             let function = self.or()?;
 
-            expr = Call {
-                id: self.get_id(),
-                callee: Box::new(function),
-                paren: pipe,
-                arguments: vec![expr],
+            let placeholders = match &function {
+                Call { arguments, .. } => {
+                    arguments.iter().filter(|arg| is_placeholder(arg)).count()
+                }
+                _ => 0,
+            };
+            if placeholders > 1 {
+                return Err(format!(
+                    "Line {}: A piped call can only have one '_' placeholder",
+                    pipe.line_number
+                ));
+            }
+
+            expr = match function {
+                Call {
+                    id,
+                    callee,
+                    paren,
+                    mut arguments,
+                } if placeholders == 1 => {
+                    let position = arguments.iter().position(is_placeholder).unwrap();
+                    arguments[position] = expr;
+                    Call {
+                        id,
+                        callee,
+                        paren,
+                        arguments,
+                    }
+                }
+                function => Call {
+                    id: self.get_id(),
+                    callee: Box::new(function),
+                    paren: pipe,
+                    arguments: vec![expr],
+                },
             };
         }
         Ok(expr)
@@ -509,7 +821,7 @@ impl Parser {
 
     fn factor(&mut self) -> Result<Expr, String> {
         let mut expr = self.unary()?;
-        while self.match_tokens(&[Slash, Star]) {
+        while self.match_tokens(&[Slash, Star, Percent, TildeSlash]) {
             let op = self.previous();
             let rhs = self.unary()?;
             expr = Binary {
@@ -524,7 +836,11 @@ impl Parser {
     }
 
     fn unary(&mut self) -> Result<Expr, String> {
-        if self.match_tokens(&[Bang, Minus]) {
+        if self.match_tokens(&[PlusPlus, MinusMinus]) {
+            let op = self.previous();
+            let target = self.unary()?;
+            self.increment(target, op, false)
+        } else if self.match_tokens(&[Bang, Minus]) {
             let op = self.previous();
             let rhs = self.unary()?;
             Ok(Unary {
@@ -533,13 +849,45 @@ impl Parser {
                 right: Box::from(rhs),
             })
         } else {
-            self.call()
+            self.power()
         }
     }
 
+    // Binds tighter than unary minus on its left, so -2 ** 2 is -4, and is
+    // right associative, so 2 ** 3 ** 2 is 2 ** 9
+    fn power(&mut self) -> Result<Expr, String> {
+        let expr = self.postfix()?;
+        if self.match_token(StarStar) {
+            let op = self.previous();
+            let rhs = self.unary()?;
+            return Ok(Binary {
+                id: self.get_id(),
+                left: Box::from(expr),
+                operator: op,
+                right: Box::from(rhs),
+            });
+        }
+
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let expr = self.call()?;
+        if self.match_tokens(&[PlusPlus, MinusMinus]) {
+            let operator = self.previous();
+            return self.increment(expr, operator, true);
+        }
+
+        Ok(expr)
+    }
+
     fn call(&mut self) -> Result<Expr, String> {
-        let mut expr = self.primary()?;
+        let expr = self.primary()?;
+        self.call_suffixes(expr)
+    }
 
+    // The calls, property accesses and indexes following an expression
+    fn call_suffixes(&mut self, mut expr: Expr) -> Result<Expr, String> {
         loop {
             if self.match_token(LeftParen) {
                 expr = self.finish_call(expr)?;
@@ -550,6 +898,15 @@ impl Parser {
                     object: Box::new(expr),
                     name,
                 };
+            } else if self.match_token(LeftBracket) {
+                let index = self.expression()?;
+                let bracket = self.consume(RightBracket, "Expected ']' after index.")?;
+                expr = Index {
+                    id: self.get_id(),
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -559,6 +916,8 @@ impl Parser {
     }
 
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, String> {
+        // Errors are reported on the line where the call starts
+        let paren = self.previous();
         let mut arguments = vec![];
 
         if !self.check(RightParen) {
@@ -577,7 +936,7 @@ impl Parser {
                 }
             }
         }
-        let paren = self.consume(RightParen, "Expected ')' after arguments.")?;
+        self.consume(RightParen, "Expected ')' after arguments.")?;
 
         Ok(Call {
             id: self.get_id(),
@@ -591,6 +950,9 @@ impl Parser {
         let token = self.peek();
         let result;
         match token.token_type {
+            LeftParen | Identifier if self.arrow_ahead() => {
+                result = self.arrow_function()?;
+            }
             LeftParen => {
                 self.advance();
                 let expr = self.expression()?;
@@ -607,6 +969,27 @@ impl Parser {
                     value: LiteralValue::from_token(token),
                 }
             }
+            // "a ${b} c" is scanned as Interpolation("a ") b InterpolationEnd
+            // StringLit(" c")
+            Interpolation => {
+                let mut parts = vec![];
+                while self.check(Interpolation) {
+                    let segment = self.advance();
+                    self.push_string_part(&mut parts, segment);
+                    parts.push(self.expression()?);
+                    self.consume(
+                        InterpolationEnd,
+                        "Expected '}' after interpolated expression.",
+                    )?;
+                }
+                let end = self.consume(StringLit, "Expected end of string.")?;
+                self.push_string_part(&mut parts, end);
+
+                result = Expr::Interpolation {
+                    id: self.get_id(),
+                    parts,
+                };
+            }
             Identifier => {
                 self.advance();
                 result = Variable {
@@ -637,12 +1020,235 @@ impl Parser {
                 self.advance();
                 result = self.function_expression()?;
             }
-            _ => return Err("Expected expression".to_string()),
+            TokenType::Match => {
+                self.advance();
+                result = self.match_expression()?;
+            }
+            LeftBracket => {
+                self.advance();
+                let mut elements = vec![];
+                while !self.check(RightBracket) {
+                    elements.push(self.expression()?);
+                    if !self.match_token(Comma) {
+                        break;
+                    }
+                }
+                self.consume(RightBracket, "Expected ']' after list elements.")?;
+                result = List {
+                    id: self.get_id(),
+                    elements,
+                };
+            }
+            // A brace never starts an expression statement, so it is a map here
+            LeftBrace => {
+                self.advance();
+                let mut entries = vec![];
+                while !self.check(RightBrace) {
+                    let key = self.peek();
+                    let key = match key.token_type {
+                        Identifier => key.lexeme.clone(),
+                        StringLit => match LiteralValue::from_token(key.clone()) {
+                            LiteralValue::StringValue(s) => s,
+                            _ => unreachable!(),
+                        },
+                        _ => {
+                            return Err(format!(
+                                "Line {}: Expected a name or a string as map key.",
+                                key.line_number
+                            ))
+                        }
+                    };
+                    self.advance();
+                    self.consume(Colon, "Expected ':' after map key.")?;
+                    entries.push((key, self.expression()?));
+                    if !self.match_token(Comma) {
+                        break;
+                    }
+                }
+                self.consume(RightBrace, "Expected '}' after map entries.")?;
+                result = Expr::Map {
+                    id: self.get_id(),
+                    entries,
+                };
+            }
+            _ => return Err(format!("Line {}: Expected expression", token.line_number)),
         }
 
         Ok(result)
     }
 
+    // match value { pattern if guard => body, ... }
+    fn match_expression(&mut self) -> Result<Expr, String> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(LeftBrace, "Expected '{' after match value.")?;
+
+        let mut arms = vec![];
+        while !self.check(RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            let guard = if self.match_token(If) {
+                let enclosing_guard = self.guard_end;
+                self.guard_end = self.guard_end_index();
+                let guard = self.expression();
+                self.guard_end = enclosing_guard;
+                Some(guard?)
+            } else {
+                None
+            };
+            self.consume(Arrow, "Expected '=>' after match pattern.")?;
+            let body = self.expression()?;
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+
+            if !self.match_token(Comma) {
+                break;
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after match arms.")?;
+
+        Ok(Expr::Match {
+            id: self.get_id(),
+            keyword,
+            value: Box::new(value),
+            arms,
+        })
+    }
+
+    // A guard ends at the first '=>' outside of brackets, so that `if x => 1`
+    // is not taken for a guard with an arrow function
+    fn guard_end_index(&self) -> Option<usize> {
+        let mut depth = 0;
+        for idx in self.current..self.tokens.len() {
+            match self.tokens[idx].token_type {
+                LeftParen | LeftBracket | LeftBrace | Interpolation => depth += 1,
+                RightParen | RightBracket | RightBrace | InterpolationEnd => depth -= 1,
+                Arrow if depth == 0 => return Some(idx),
+                _ => (),
+            }
+            if depth < 0 {
+                break;
+            }
+        }
+
+        None
+    }
+
+    // _, name, 1, -1, "x", true, nil, "x" | "y", [a, b], {key, other: pattern},
+    // Class{field, other: pattern}
+    fn pattern(&mut self) -> Result<Pattern, String> {
+        let first = self.primary_pattern()?;
+        if !self.check(Bar) {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec![first];
+        while self.match_token(Bar) {
+            alternatives.push(self.primary_pattern()?);
+        }
+        let pattern = Pattern::Alternatives(alternatives);
+        if let Some(name) = pattern.bindings().first() {
+            return Err(format!(
+                "Line {}: Alternative patterns can't bind names like '{}'",
+                name.line_number, name.lexeme
+            ));
+        }
+
+        Ok(pattern)
+    }
+
+    fn primary_pattern(&mut self) -> Result<Pattern, String> {
+        let token = self.advance();
+        match token.token_type {
+            Identifier if token.lexeme == "_" => Ok(Pattern::Wildcard),
+            Identifier if self.match_token(LeftBrace) => {
+                let fields = self.pattern_entries("field")?;
+                Ok(Pattern::Instance {
+                    class: token,
+                    fields,
+                })
+            }
+            Identifier => Ok(Pattern::Binding(token)),
+            False | True | Nil | Number | StringLit => {
+                Ok(Pattern::Literal(LiteralValue::from_token(token)))
+            }
+            Minus if self.check(Number) => match LiteralValue::from_token(self.advance()) {
+                LiteralValue::Number(x) => Ok(Pattern::Literal(LiteralValue::Number(-x))),
+                _ => unreachable!(),
+            },
+            LeftBracket => {
+                let mut elements = vec![];
+                while !self.check(RightBracket) {
+                    elements.push(self.pattern()?);
+                    if !self.match_token(Comma) {
+                        break;
+                    }
+                }
+                self.consume(RightBracket, "Expected ']' after list pattern.")?;
+                Ok(Pattern::List(elements))
+            }
+            LeftBrace => Ok(Pattern::Map(self.pattern_entries("key")?)),
+            _ => Err(format!(
+                "Line {}: Expected a pattern but got '{}'",
+                token.line_number, token.lexeme
+            )),
+        }
+    }
+
+    // The entries of a map or instance pattern after the '{'. A name alone
+    // binds the entry to that name.
+    fn pattern_entries(&mut self, kind: &str) -> Result<Vec<(String, Pattern)>, String> {
+        let mut entries = vec![];
+        while !self.check(RightBrace) {
+            let key = self.advance();
+            let name = match key.token_type {
+                Identifier => key.lexeme.clone(),
+                StringLit if kind == "key" => match LiteralValue::from_token(key.clone()) {
+                    LiteralValue::StringValue(s) => s,
+                    _ => unreachable!(),
+                },
+                _ => {
+                    return Err(format!(
+                        "Line {}: Expected a {kind} name in pattern.",
+                        key.line_number
+                    ))
+                }
+            };
+
+            let pattern = if self.match_token(Colon) {
+                self.pattern()?
+            } else if key.token_type == Identifier {
+                Pattern::Binding(key)
+            } else {
+                return Err(format!(
+                    "Line {}: Expected ':' after {kind} in pattern.",
+                    key.line_number
+                ));
+            };
+            entries.push((name, pattern));
+
+            if !self.match_token(Comma) {
+                break;
+            }
+        }
+        self.consume(RightBrace, &format!("Expected '}}' after {kind} patterns."))?;
+
+        Ok(entries)
+    }
+
+    // Empty text around interpolated expressions is left out
+    fn push_string_part(&mut self, parts: &mut Vec<Expr>, token: Token) {
+        let value = LiteralValue::from_token(token);
+        if value != LiteralValue::StringValue(String::new()) {
+            parts.push(Literal {
+                id: self.get_id(),
+                value,
+            });
+        }
+    }
+
     fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<Token, String> {
         let token = self.peek();
         if token.token_type == token_type {
@@ -710,7 +1316,7 @@ impl Parser {
             }
 
             match self.peek().token_type {
-                Class | Fun | Var | For | If | While | Print | Return => return,
+                Class | Fun | Var | Const | For | If | While | Print | Return => return,
                 _ => (),
             }
 
@@ -778,6 +1384,23 @@ mod tests {
         assert_eq!(string_expr, "(== (+ 1 2) (+ 5 7))");
     }
 
+    #[test]
+    fn test_ids_continue_across_parsers() {
+        let tokens = Scanner::new("a + b;").scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        parser.parse().unwrap();
+        let next_id = parser.next_id();
+        assert_eq!(next_id, 3);
+
+        let tokens = Scanner::new("c;").scan_tokens().unwrap();
+        let mut parser = Parser::starting_at(tokens, next_id);
+        let parsed = parser.parse().unwrap();
+        match &parsed[0] {
+            Stmt::Expression { expression } => assert_eq!(expression.get_id(), 3),
+            _ => panic!("Expected an expression statement"),
+        }
+    }
+
     #[test]
     fn test_eq_with_paren() {
         let source = "1 == (2 + 2);";
@@ -789,4 +1412,170 @@ mod tests {
 
         assert_eq!(string_expr, "(== 1 (group (+ 2 2)))");
     }
+
+    #[test]
+    fn test_string_interpolation() {
+        let source = "\"a ${b + 1} c${d}\";";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        assert_eq!(
+            parsed[0].to_string(),
+            "(str \"a \" (+ (var b) 1) \" c\" (var d))"
+        );
+
+        let tokens = Scanner::new("1;\n\"a ${b +} c\";").scan_tokens().unwrap();
+        let err = Parser::new(tokens).parse().unwrap_err();
+        assert!(err.contains("Line 2: Expected expression"));
+    }
+
+    #[test]
+    fn test_compound_assignment() {
+        let source = "a -= 1; this.n *= 2; a[i] %= 3; f().n += 4; ++a; a--; xs[0]++; - -a;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        let strings = parsed
+            .iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            strings,
+            vec![
+                "(-= a 1)",
+                "(set*= (this) n 2)",
+                "(set-index%= (var a) (var i) 3)",
+                "(set+= (call (var f)) n 4)",
+                "(+= a 1)",
+                "(-= a 1 postfix)",
+                "(set-index+= (var xs) 0 1 postfix)",
+                "(- (- (var a)))",
+            ]
+        );
+
+        for source in ["f() += 1;", "++1;", "(a)--;"] {
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            assert!(
+                Parser::new(tokens).parse().is_err(),
+                "{source} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn test_pipe_placeholders() {
+        let source = "x |> f(_, 1) |> g(2, _) |> h;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        assert_eq!(
+            parsed[0].to_string(),
+            "(call (var h) (call (var g) 2 (call (var f) (var x) 1)))"
+        );
+
+        let tokens = Scanner::new("xs |> .map(f).len() |> g;")
+            .scan_tokens()
+            .unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        assert_eq!(
+            parsed[0].to_string(),
+            "(call (var g) (call (get (call (get (var xs) map) (var f)) len)))"
+        );
+
+        let tokens = Scanner::new("x |> f(_, _);").scan_tokens().unwrap();
+        let err = Parser::new(tokens).parse().unwrap_err();
+        assert!(err.contains("Line 1: A piped call can only have one '_' placeholder"));
+    }
+
+    #[test]
+    fn test_conditional() {
+        let source = "x = a ? b : c ? d : e; f |> g ? 1 : 2;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        assert_eq!(
+            parsed[0].to_string(),
+            "(= x (? (var a) (var b) (? (var c) (var d) (var e))))"
+        );
+        assert_eq!(parsed[1].to_string(), "(? (call (var g) (var f)) 1 2)");
+
+        let tokens = Scanner::new("a ? b;").scan_tokens().unwrap();
+        let err = Parser::new(tokens).parse().unwrap_err();
+        assert!(err.contains("Line 1: Expected ':'"));
+    }
+
+    #[test]
+    fn test_match() {
+        let source = r#"match v { -1 | "a" => 1, [a, {b, c: [_]}] if a => b, P{x} => x };"#;
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        assert_eq!(
+            parsed[0].to_string(),
+            r#"(match (var v) (arm -1 | "a" 1) (arm [a, {b, c: [_]}] (if (var a)) (var b)) (arm P{x} (var x)))"#
+        );
+
+        let tokens = Scanner::new("match v { [a] | b => 1 };")
+            .scan_tokens()
+            .unwrap();
+        let err = Parser::new(tokens).parse().unwrap_err();
+        assert!(err.contains("Line 1: Alternative patterns can't bind names like 'a'"));
+
+        let tokens = Scanner::new("match v { a + 1 => 1 };")
+            .scan_tokens()
+            .unwrap();
+        assert!(Parser::new(tokens).parse().is_err());
+    }
+
+    #[test]
+    fn test_destructuring() {
+        let source = "var [a, {b}] = x; fun f(p, [q, r]) { return q; } ([s]) => s;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        let strings = parsed
+            .iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            strings,
+            vec![
+                "(var [a, {b}] (var x))",
+                "(fun f (p <parameter 2>) (var [q, r] (var <parameter 2>)) (return (var q)))",
+                "(fun (<parameter 1>) (var [s] (var <parameter 1>)) (return (var s)))",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_constants() {
+        let source = "const a = 1; const [b, c] = x;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        let strings = parsed
+            .iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(strings, vec!["(const a 1)", "(const [b, c] (var x))"]);
+
+        let tokens = Scanner::new("const a;").scan_tokens().unwrap();
+        assert_eq!(
+            Parser::new(tokens).parse().err(),
+            Some("Line 1: Expected '=' after constant name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_arrow_functions() {
+        let source = "x => x * 2; (a, b) => a |> b; () => 1; (a) + b;";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let parsed = Parser::new(tokens).parse().unwrap();
+        let strings = parsed
+            .iter()
+            .map(|stmt| stmt.to_string())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            strings,
+            vec![
+                "(fun (x) (return (* (var x) 2)))",
+                "(fun (a b) (return (call (var b) (var a))))",
+                "(fun () (return 1))",
+                "(+ (group (var a)) (var b))",
+            ]
+        );
+    }
 }
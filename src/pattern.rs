@@ -0,0 +1,257 @@
+use crate::expr::LiteralValue;
+use crate::scanner::Token;
+use std::fmt;
+
+// Patterns take values apart in `match` arms and destructuring declarations
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // _ matches anything without binding it
+    Wildcard,
+    // A name matches anything and binds it
+    Binding(Token),
+    // 1, "x", true, nil
+    Literal(LiteralValue),
+    // "x" | "y"
+    Alternatives(Vec<Pattern>),
+    // [a, b] matches lists of exactly that length
+    List(Vec<Pattern>),
+    // {name, age: 42} matches maps with at least these keys
+    Map(Vec<(String, Pattern)>),
+    // Point{x, y} matches instances of Point and its subclasses
+    Instance {
+        class: Token,
+        fields: Vec<(String, Pattern)>,
+    },
+}
+
+// Whether the class or one of its superclasses has the name
+fn is_instance_of(class: &LiteralValue, name: &str) -> bool {
+    match class {
+        LiteralValue::LoxClass {
+            name: class_name,
+            methods: _,
+            superclass,
+        } => {
+            class_name == name
+                || superclass
+                    .as_ref()
+                    .is_some_and(|superclass| is_instance_of(superclass, name))
+        }
+        _ => false,
+    }
+}
+
+fn entries_to_string(entries: &[(String, Pattern)]) -> String {
+    entries
+        .iter()
+        .map(|(key, pattern)| match pattern {
+            Pattern::Binding(name) if name.lexeme == *key => key.clone(),
+            pattern => format!("{key}: {pattern}"),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl Pattern {
+    // The names bound by the pattern, in order
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Wildcard | Pattern::Literal(_) => vec![],
+            Pattern::Binding(name) => vec![name],
+            Pattern::Alternatives(patterns) | Pattern::List(patterns) => {
+                patterns.iter().flat_map(|p| p.bindings()).collect()
+            }
+            Pattern::Map(entries)
+            | Pattern::Instance {
+                fields: entries, ..
+            } => entries
+                .iter()
+                .flat_map(|(_, pattern)| pattern.bindings())
+                .collect(),
+        }
+    }
+
+    // Matches the value against the pattern and collects the bound values.
+    // A value that does not match gives an error describing why.
+    pub fn bind(
+        &self,
+        value: &LiteralValue,
+        bound: &mut Vec<(String, LiteralValue)>,
+    ) -> Result<(), String> {
+        match self {
+            Pattern::Wildcard => Ok(()),
+            Pattern::Binding(name) => {
+                bound.push((name.lexeme.clone(), value.clone()));
+                Ok(())
+            }
+            Pattern::Literal(literal) => {
+                if literal == value {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Expected {} but got {}",
+                        literal.to_string(),
+                        value.to_string()
+                    ))
+                }
+            }
+            Pattern::Alternatives(patterns) => {
+                let mut alternative = vec![];
+                let matched = patterns.iter().any(|p| {
+                    alternative.clear();
+                    p.bind(value, &mut alternative).is_ok()
+                });
+                if matched {
+                    bound.extend(alternative);
+                    Ok(())
+                } else {
+                    Err(format!("Expected {self} but got {}", value.to_string()))
+                }
+            }
+            Pattern::List(patterns) => {
+                let elements = match value {
+                    LiteralValue::List(elements) => elements.borrow().clone(),
+                    other => return Err(format!("Expected a List but got {}", other.to_type())),
+                };
+                if elements.len() != patterns.len() {
+                    return Err(format!(
+                        "Expected a List of length {} but got one of length {}",
+                        patterns.len(),
+                        elements.len()
+                    ));
+                }
+
+                for (pattern, element) in patterns.iter().zip(elements.iter()) {
+                    pattern.bind(element, bound)?;
+                }
+                Ok(())
+            }
+            Pattern::Map(entries) => {
+                let map = match value {
+                    LiteralValue::Map(map) => map.borrow().clone(),
+                    other => return Err(format!("Expected a Map but got {}", other.to_type())),
+                };
+
+                for (key, pattern) in entries {
+                    match map.iter().find(|(k, _)| k == key) {
+                        Some((_, value)) => pattern.bind(value, bound)?,
+                        None => return Err(format!("Expected a Map with the key '{key}'")),
+                    }
+                }
+                Ok(())
+            }
+            Pattern::Instance { class, fields } => {
+                let values = match value {
+                    LiteralValue::LoxInstance {
+                        class: value_class,
+                        fields,
+                    } if is_instance_of(value_class, &class.lexeme) => fields.borrow().clone(),
+                    other => {
+                        return Err(format!(
+                            "Expected an instance of {} but got {}",
+                            class.lexeme,
+                            other.to_type()
+                        ))
+                    }
+                };
+
+                for (field, pattern) in fields {
+                    match values.iter().find(|(name, _)| name == field) {
+                        Some((_, value)) => pattern.bind(value, bound)?,
+                        None => {
+                            return Err(format!(
+                                "Expected {} to have the field '{field}'",
+                                class.lexeme
+                            ))
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Wildcard => write!(f, "_"),
+            Pattern::Binding(name) => write!(f, "{}", name.lexeme),
+            Pattern::Literal(literal) => write!(f, "{}", literal.to_string()),
+            Pattern::Alternatives(patterns) => write!(
+                f,
+                "{}",
+                patterns
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" | ")
+            ),
+            Pattern::List(patterns) => write!(
+                f,
+                "[{}]",
+                patterns
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Pattern::Map(entries) => write!(f, "{{{}}}", entries_to_string(entries)),
+            Pattern::Instance { class, fields } => {
+                write!(f, "{}{{{}}}", class.lexeme, entries_to_string(fields))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::TokenType;
+
+    fn name(lexeme: &str) -> Pattern {
+        Pattern::Binding(Token {
+            token_type: TokenType::Identifier,
+            lexeme: lexeme.to_string(),
+            literal: None,
+            line_number: 1,
+        })
+    }
+
+    #[test]
+    fn bind_lists_and_maps() {
+        let pattern = Pattern::List(vec![name("a"), Pattern::Wildcard, name("b")]);
+        let value = LiteralValue::from_list(vec![
+            LiteralValue::Number(1.0),
+            LiteralValue::Nil,
+            LiteralValue::StringValue("x".to_string()),
+        ]);
+        let mut bound = vec![];
+        pattern.bind(&value, &mut bound).unwrap();
+        assert_eq!(
+            bound,
+            vec![
+                ("a".to_string(), LiteralValue::Number(1.0)),
+                ("b".to_string(), LiteralValue::StringValue("x".to_string())),
+            ]
+        );
+        assert_eq!(pattern.to_string(), "[a, _, b]");
+
+        let short = LiteralValue::from_list(vec![LiteralValue::Nil]);
+        assert_eq!(
+            pattern.bind(&short, &mut vec![]),
+            Err("Expected a List of length 3 but got one of length 1".to_string())
+        );
+
+        let pattern = Pattern::Map(vec![("age".to_string(), name("age"))]);
+        let map = LiteralValue::from_map(vec![("name".to_string(), LiteralValue::Nil)]);
+        assert_eq!(
+            pattern.bind(&map, &mut vec![]),
+            Err("Expected a Map with the key 'age'".to_string())
+        );
+        assert_eq!(
+            pattern.bind(&LiteralValue::Number(1.0), &mut vec![]),
+            Err("Expected a Map but got Number".to_string())
+        );
+    }
+}
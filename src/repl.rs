@@ -0,0 +1,278 @@
+use crate::expr::LiteralValue;
+use crate::interpreter::Interpreter;
+use crate::line_editor::{Input, LineEditor};
+use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::scanner::{Scanner, TokenType};
+use crate::stdlib::process;
+use crate::stmt::Stmt;
+use std::fs;
+use std::time::Instant;
+
+// Input is incomplete while a string is left open, a paren or brace is left
+// unbalanced or the last statement is missing its ';'. The REPL keeps
+// reading continuation lines until the input is complete.
+fn is_incomplete(source: &str) -> bool {
+    let tokens = match Scanner::new(source).scan_tokens() {
+        Ok(tokens) => tokens,
+        Err(msg) => return msg.starts_with("Unterminated"),
+    };
+
+    let mut depth = 0;
+    for token in &tokens {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+            _ => (),
+        }
+    }
+    if depth > 0 {
+        return true;
+    }
+
+    let last = tokens
+        .iter()
+        .rev()
+        .find(|token| token.token_type != TokenType::Eof);
+    match last {
+        None => false,
+        Some(token) => !matches!(
+            token.token_type,
+            TokenType::Semicolon | TokenType::RightBrace
+        ),
+    }
+}
+
+// A REPL session keeps one interpreter, resolver and expression id space
+// across inputs, so that ids resolved on earlier lines never collide with
+// the ids of later ones.
+struct Session {
+    interpreter: Interpreter,
+    resolver: Resolver,
+    next_id: usize,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            interpreter: Interpreter::new(),
+            resolver: Resolver::new(),
+            next_id: 0,
+        }
+    }
+
+    // Like `run`, but a lone expression statement has its value printed
+    fn run_input(&mut self, contents: &str) -> Result<(), String> {
+        let mut scanner = Scanner::new(contents);
+        let tokens = scanner.scan_tokens()?;
+
+        let mut parser = Parser::starting_at(tokens, self.next_id);
+        let stmts = parser.parse();
+        self.next_id = parser.next_id();
+        let stmts = stmts?;
+
+        let locals = self.resolver.resolve_input(&stmts.iter().collect())?;
+
+        self.interpreter.resolve(locals);
+
+        if let [Stmt::Expression { expression }] = stmts.as_slice() {
+            let value = expression.evaluate(self.interpreter.environment.clone())?;
+            if value != LiteralValue::Nil {
+                println!("{}", value.to_string());
+            }
+            return Ok(());
+        }
+
+        self.interpreter.interpret(stmts.iter().collect())
+    }
+}
+
+const HELP: &str = "\
+Enter statements or expressions; the value of an expression statement is printed.
+Input is continued on the next line until it is complete, an empty line submits it as is.
+
+Commands:
+  :help          Show this message
+  :env           Show the variables defined in the session
+  :load <file>   Run a file in the session
+  :reset         Start a new session
+  :ast <expr>    Show the syntax tree of an expression
+  :tokens <src>  Show the tokens scanned from the source
+  :time <src>    Run the source and show how long it took";
+
+fn parse_expression(source: &str) -> Result<String, String> {
+    let mut source = source.trim().to_string();
+    if !source.ends_with(';') {
+        source.push(';');
+    }
+
+    let tokens = Scanner::new(&source).scan_tokens()?;
+    let stmts = Parser::new(tokens).parse()?;
+    match stmts.as_slice() {
+        [Stmt::Expression { expression }] => Ok(expression.to_string()),
+        _ => Err("Expected a single expression".to_string()),
+    }
+}
+
+fn run_command(session: &mut Session, command: &str) -> Result<(), String> {
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (command, ""),
+    };
+
+    match name {
+        ":help" => println!("{HELP}"),
+        ":env" => print!("{}", session.interpreter.environment.dump(0)),
+        ":load" => {
+            let contents = fs::read_to_string(arg).map_err(|e| format!("{arg}: {e}"))?;
+            session.run_input(&contents)?;
+        }
+        ":reset" => *session = Session::new(),
+        ":ast" => println!("{}", parse_expression(arg)?),
+        ":tokens" => {
+            for token in Scanner::new(arg).scan_tokens()? {
+                println!("{}", token.to_string());
+            }
+        }
+        ":time" => {
+            let start = Instant::now();
+            let result = session.run_input(arg);
+            println!("Took {:?}", start.elapsed());
+            result?;
+        }
+        _ => return Err(format!("Unknown command {name}, see :help")),
+    }
+
+    Ok(())
+}
+
+pub fn run_prompt() -> Result<(), String> {
+    let mut editor = LineEditor::new();
+    let mut session = Session::new();
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        let line = match editor.read_line(prompt)? {
+            Input::Line(line) => line,
+            Input::Interrupted => {
+                input.clear();
+                continue;
+            }
+            Input::Eof => {
+                println!();
+                return Ok(());
+            }
+        };
+        editor.add_history(&line);
+
+        if input.is_empty() && line.trim_start().starts_with(':') {
+            let result = run_command(&mut session, line.trim());
+            if process::exit_requested() {
+                return Ok(());
+            }
+            if let Err(msg) = result {
+                println!("{}", msg);
+            }
+            continue;
+        }
+
+        // An empty continuation line submits the input as it is
+        let blank = line.trim().is_empty();
+        if blank && input.trim().is_empty() {
+            input.clear();
+            continue;
+        }
+
+        input.push_str(&line);
+        input.push('\n');
+        if !blank && is_incomplete(&input) {
+            continue;
+        }
+
+        let result = session.run_input(&input);
+        if process::exit_requested() {
+            return Ok(());
+        }
+        if let Err(msg) = result {
+            println!("{}", msg);
+        }
+        input.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete_input() {
+        assert!(!is_incomplete("var a = 1;\n"));
+        assert!(!is_incomplete("fun f(a) {\n  return a;\n}\n"));
+        assert!(!is_incomplete("// just a comment\n"));
+    }
+
+    #[test]
+    fn closures_resolve_across_inputs() {
+        let mut session = Session::new();
+        session
+            .run_input("fun mk() { var c = 10; fun g() { return c; } return g; }\n")
+            .unwrap();
+        session.run_input("var g = mk();\n").unwrap();
+        session.run_input("{ var q = 5; q = q + 1; }\n").unwrap();
+        session.run_input("var result = g();\n").unwrap();
+
+        let result = session.interpreter.environment.get("result", usize::MAX);
+        assert_eq!(result, Some(LiteralValue::Number(10.0)));
+    }
+
+    #[test]
+    fn constants_across_inputs() {
+        let mut session = Session::new();
+        session.run_input("const limit = 3;\n").unwrap();
+        assert_eq!(
+            session.run_input("limit = 4;\n"),
+            Err("Cannot assign to constant 'limit'".to_string())
+        );
+        assert_eq!(
+            session.run_input("var limit = 5;\n"),
+            Err("Cannot redeclare constant 'limit'".to_string())
+        );
+    }
+
+    #[test]
+    fn env_command() {
+        let mut session = Session::new();
+        assert_eq!(session.interpreter.environment.dump(0), "");
+
+        session
+            .run_input("var b = [1]; fun a() {} fun len <- \"wc\";\n")
+            .unwrap();
+        assert_eq!(
+            session.interpreter.environment.dump(0),
+            "a = a/0\nb = [1]\nlen = len/0\n"
+        );
+    }
+
+    #[test]
+    fn ast_command() {
+        assert_eq!(parse_expression("1 + 2 * 3").unwrap(), "(+ 1 (* 2 3))");
+        assert!(parse_expression("var a = 1;").is_err());
+    }
+
+    #[test]
+    fn reset_command() {
+        let mut session = Session::new();
+        session.run_input("var a = 1;\n").unwrap();
+        run_command(&mut session, ":reset").unwrap();
+        assert!(session.run_input("a;\n").is_err());
+        assert!(run_command(&mut session, ":nope").is_err());
+    }
+
+    #[test]
+    fn incomplete_input() {
+        assert!(is_incomplete("fun f(a) {\n"));
+        assert!(is_incomplete("print f(1,\n"));
+        assert!(is_incomplete("var a = \"abc\n"));
+        assert!(is_incomplete("var a = 1\n"));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::expr::Expr;
 use crate::scanner::Token;
 use crate::stmt::Stmt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Copy, Clone, PartialEq)]
 enum FunctionType {
@@ -10,19 +10,33 @@ enum FunctionType {
     Method,
 }
 
+// How a variable reference was resolved: the scope depth of its binding,
+// or None if it is assumed to be global
+pub struct Resolution {
+    pub id: usize,
+    pub name: Token,
+    pub depth: Option<usize>,
+}
+
 #[allow(dead_code)]
 pub struct Resolver {
     scopes: Vec<HashMap<String, bool>>,
+    // The names declared with `const`, first for the globals and then for
+    // each of the scopes
+    constants: Vec<HashSet<String>>,
     current_function: FunctionType,
     locals: HashMap<usize, usize>,
+    resolutions: Vec<Resolution>,
 }
 
 impl Resolver {
     pub fn new() -> Self {
         Self {
             scopes: vec![],
+            constants: vec![HashSet::new()],
             current_function: FunctionType::None,
             locals: HashMap::new(),
+            resolutions: vec![],
         }
     }
 
@@ -32,7 +46,25 @@ impl Resolver {
             Stmt::Var {
                 name: _,
                 initializer: _,
+                constant: _,
             } => self.resolve_var(stmt)?,
+            Stmt::Destructure {
+                keyword: _,
+                pattern,
+                initializer,
+                constant,
+            } => {
+                for name in pattern.bindings() {
+                    self.declare(name)?;
+                }
+                self.resolve_expr(initializer)?;
+                for name in pattern.bindings() {
+                    self.define(name);
+                    if *constant {
+                        self.define_constant(name);
+                    }
+                }
+            }
             Stmt::Class {
                 name,
                 methods,
@@ -104,6 +136,15 @@ impl Resolver {
                 self.resolve_expr(condition)?;
                 self.resolve_internal(body.as_ref())?;
             }
+            Stmt::Test { name: _, body } => {
+                if self.current_function != FunctionType::None || !self.scopes.is_empty() {
+                    return Err("Test blocks are only allowed at the top level".to_string());
+                }
+
+                self.begin_scope();
+                self.resolve_many(&body.iter().collect())?;
+                self.end_scope();
+            }
         }
         Ok(())
     }
@@ -121,6 +162,28 @@ impl Resolver {
         Ok(self.locals)
     }
 
+    pub fn resolutions(mut self, stmts: &Vec<&Stmt>) -> Result<Vec<Resolution>, String> {
+        self.resolve_many(stmts)?;
+        self.resolutions.sort_by_key(|resolution| resolution.id);
+        Ok(self.resolutions)
+    }
+
+    // Resolves one more top-level input of a session (e.g. a REPL line) and
+    // returns the locals found in it. The resolver is reset to the top level
+    // on failure so that the next input starts from a clean state.
+    pub fn resolve_input(&mut self, stmts: &Vec<&Stmt>) -> Result<HashMap<usize, usize>, String> {
+        let result = self.resolve_many(stmts);
+        let locals = std::mem::take(&mut self.locals);
+        self.resolutions.clear();
+        if result.is_err() {
+            self.scopes.clear();
+            self.constants.truncate(1);
+            self.current_function = FunctionType::None;
+        }
+
+        result.map(|_| locals)
+    }
+
     fn resolve_block(&mut self, stmt: &Stmt) -> Result<(), String> {
         match stmt {
             Stmt::Block { statements } => {
@@ -135,10 +198,18 @@ impl Resolver {
     }
 
     fn resolve_var(&mut self, stmt: &Stmt) -> Result<(), String> {
-        if let Stmt::Var { name, initializer } = stmt {
+        if let Stmt::Var {
+            name,
+            initializer,
+            constant,
+        } = stmt
+        {
             self.declare(name)?;
             self.resolve_expr(initializer)?;
             self.define(name);
+            if *constant {
+                self.define_constant(name);
+            }
         } else if let Stmt::CmdFunction {name, cmd: _} = stmt {
             self.declare(name)?;
             self.define(name);
@@ -204,13 +275,20 @@ impl Resolver {
 
     fn begin_scope(&mut self) {
         self.scopes.push(HashMap::new());
+        self.constants.push(HashSet::new());
     }
 
     fn end_scope(&mut self) {
         self.scopes.pop().expect("Stack underflow");
+        self.constants.pop();
     }
 
     fn declare(&mut self, name: &Token) -> Result<(), String> {
+        // Also for globals, which can otherwise be declared again
+        if self.constants.last().unwrap().contains(&name.lexeme) {
+            return Err(format!("Cannot redeclare constant '{}'", name.lexeme));
+        }
+
         let size = self.scopes.len();
         if self.scopes.is_empty() {
             return Ok(());
@@ -234,6 +312,22 @@ impl Resolver {
         self.scopes[size - 1].insert(name.lexeme.clone(), true);
     }
 
+    fn define_constant(&mut self, name: &Token) {
+        self.constants
+            .last_mut()
+            .unwrap()
+            .insert(name.lexeme.clone());
+    }
+
+    // Whether the name resolves to a `const` declaration
+    fn is_constant(&self, name: &Token) -> bool {
+        let scope = (0..self.scopes.len())
+            .rev()
+            .find(|i| self.scopes[*i].contains_key(&name.lexeme))
+            .map_or(0, |i| i + 1);
+        self.constants[scope].contains(&name.lexeme)
+    }
+
     // (i > j) may require different resolution distances
     // { var a = 2; fun fn() { return a;} { var a = 1; var b = fn(); } }
     // (i > 3) -> take id -> store resolution distance
@@ -248,6 +342,19 @@ impl Resolver {
                 name: _,
                 value: _,
             } => self.resolve_expr_assign(expr, expr.get_id()),
+            Expr::CompoundAssign {
+                id: _,
+                name,
+                operator: _,
+                value,
+                postfix: _,
+            } => {
+                if self.is_constant(name) {
+                    return Err(format!("Cannot assign to constant '{}'", name.lexeme));
+                }
+                self.resolve_expr(value)?;
+                self.resolve_local(name, expr.get_id())
+            }
             Expr::Binary {
                 id: _,
                 left,
@@ -270,12 +377,52 @@ impl Resolver {
 
                 Ok(())
             }
+            Expr::Conditional {
+                id: _,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition)?;
+                self.resolve_expr(then_branch)?;
+                self.resolve_expr(else_branch)
+            }
             Expr::Get {
                 id: _,
                 object,
                 name: _,
             } => self.resolve_expr(object),
             Expr::Grouping { id: _, expression } => self.resolve_expr(expression),
+            Expr::Index {
+                id: _,
+                object,
+                bracket: _,
+                index,
+            } => {
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)
+            }
+            Expr::Interpolation { id: _, parts } => {
+                for part in parts {
+                    self.resolve_expr(part)?;
+                }
+
+                Ok(())
+            }
+            Expr::List { id: _, elements } => {
+                for element in elements {
+                    self.resolve_expr(element)?;
+                }
+
+                Ok(())
+            }
+            Expr::Map { id: _, entries } => {
+                for (_, value) in entries {
+                    self.resolve_expr(value)?;
+                }
+
+                Ok(())
+            }
             Expr::Literal { id: _, value: _ } => Ok(()),
             Expr::Logical {
                 id: _,
@@ -286,6 +433,28 @@ impl Resolver {
                 self.resolve_expr(left)?;
                 self.resolve_expr(right)
             }
+            Expr::Match {
+                id: _,
+                keyword: _,
+                value,
+                arms,
+            } => {
+                self.resolve_expr(value)?;
+                for arm in arms {
+                    self.begin_scope();
+                    for name in arm.pattern.bindings() {
+                        self.declare(name)?;
+                        self.define(name);
+                    }
+                    if let Some(guard) = &arm.guard {
+                        self.resolve_expr(guard)?;
+                    }
+                    self.resolve_expr(&arm.body)?;
+                    self.end_scope();
+                }
+
+                Ok(())
+            }
             Expr::Set {
                 id: _,
                 object,
@@ -295,6 +464,41 @@ impl Resolver {
                 self.resolve_expr(value)?;
                 self.resolve_expr(object)
             }
+            Expr::SetIndex {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                value,
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)
+            }
+            Expr::CompoundSet {
+                id: _,
+                object,
+                name: _,
+                operator: _,
+                value,
+                postfix: _,
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)
+            }
+            Expr::CompoundIndexSet {
+                id: _,
+                object,
+                bracket: _,
+                index,
+                operator: _,
+                value,
+                postfix: _,
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)?;
+                self.resolve_expr(index)
+            }
             Expr::This { id: _, keyword } => {
                 if self.current_function != FunctionType::Method {
                     return Err("Cannot use 'this' keyword outside of a class".to_string());
@@ -324,11 +528,20 @@ impl Resolver {
                 paren: _,
                 arguments,
                 body,
-            } => self.resolve_function_helper(
-                arguments,
-                &body.iter().map(|b| b.as_ref()).collect(),
-                FunctionType::Function,
-            ),
+            } => {
+                // Anonymous functions in a method, like `() => this.n`, can
+                // use its `this`
+                let fn_type = if self.current_function == FunctionType::Method {
+                    FunctionType::Method
+                } else {
+                    FunctionType::Function
+                };
+                self.resolve_function_helper(
+                    arguments,
+                    &body.iter().map(|b| b.as_ref()).collect(),
+                    fn_type,
+                )
+            }
         }
     }
 
@@ -358,24 +571,29 @@ impl Resolver {
 
     fn resolve_local(&mut self, name: &Token, resolve_id: usize) -> Result<(), String> {
         let size = self.scopes.len();
-        if size == 0 {
-            return Ok(());
-        }
-
-        for i in (0..=(size - 1)).rev() {
-            let scope = &self.scopes[i];
-            if scope.contains_key(&name.lexeme) {
-                self.locals.insert(resolve_id, size - 1 - i);
-                return Ok(());
-            }
+        let depth = (0..size)
+            .rev()
+            .find(|i| self.scopes[*i].contains_key(&name.lexeme))
+            .map(|i| size - 1 - i);
+
+        // Not found in any scope: assume it's global
+        if let Some(depth) = depth {
+            self.locals.insert(resolve_id, depth);
         }
+        self.resolutions.push(Resolution {
+            id: resolve_id,
+            name: name.clone(),
+            depth,
+        });
 
-        // Assume it's global
         Ok(())
     }
 
     fn resolve_expr_assign(&mut self, expr: &Expr, resolve_id: usize) -> Result<(), String> {
         if let Expr::Assign { id: _, name, value } = expr {
+            if self.is_constant(name) {
+                return Err(format!("Cannot assign to constant '{}'", name.lexeme));
+            }
             self.resolve_expr(value.as_ref())?;
             self.resolve_local(name, resolve_id)?;
         } else {
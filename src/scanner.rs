@@ -2,27 +2,32 @@ use std::collections::HashMap;
 use std::string::String;
 
 fn is_digit(ch: char) -> bool {
-    ch as u8 >= '0' as u8 && ch as u8 <= '9' as u8
+    ch.is_ascii_digit()
 }
 
+// Names follow Unicode's identifier rules (XID_Start, or '_', followed by
+// XID_Continue), so they may use any script, like `größe` or `π`, and
+// combining marks, but not symbols like `²` or `½`. Number literals only
+// use ASCII digits
 fn is_alpha(ch: char) -> bool {
-    let uch = ch as u8;
-    (uch >= 'a' as u8 && uch <= 'z' as u8) || (uch >= 'A' as u8 && uch <= 'Z' as u8) || (ch == '_')
+    unicode_ident::is_xid_start(ch) || ch == '_'
 }
 
 fn is_alpha_numeric(ch: char) -> bool {
-    is_alpha(ch) || is_digit(ch)
+    unicode_ident::is_xid_continue(ch)
 }
 
 fn get_keywords_hashmap() -> HashMap<&'static str, TokenType> {
     HashMap::from([
         ("and", And),
         ("class", Class),
+        ("const", Const),
         ("else", Else),
         ("false", False),
         ("for", For),
         ("fun", Fun),
         ("if", If),
+        ("match", Match),
         ("nil", Nil),
         ("or", Or),
         ("print", Print),
@@ -36,11 +41,14 @@ fn get_keywords_hashmap() -> HashMap<&'static str, TokenType> {
 }
 
 pub struct Scanner {
-    source: String,
+    // Scanned by char, so that `start` and `current` never split one
+    source: Vec<char>,
     tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: usize,
+    // Open braces inside each `${...}` being scanned, innermost last
+    interpolations: Vec<usize>,
 
     keywords: HashMap<&'static str, TokenType>,
 }
@@ -48,16 +56,24 @@ pub struct Scanner {
 impl Scanner {
     pub fn new(source: &str) -> Self {
         Self {
-            source: source.to_string(),
+            source: source.chars().collect(),
             tokens: vec![],
             start: 0,
             current: 0,
             line: 1,
+            interpolations: vec![],
             keywords: get_keywords_hashmap(),
         }
     }
 
     pub fn scan_tokens(self: &mut Self) -> Result<Vec<Token>, String> {
+        // Skip a shebang line, so that scripts can be made executable
+        if self.source.starts_with(&['#', '!']) {
+            while self.peek() != '\n' && !self.is_at_end() {
+                self.advance();
+            }
+        }
+
         let mut errors = vec![];
         while !self.is_at_end() {
             self.start = self.current;
@@ -67,6 +83,10 @@ impl Scanner {
             }
         }
 
+        if !self.interpolations.is_empty() {
+            errors.push("Unterminated string interpolation".to_string());
+        }
+
         self.tokens.push(Token {
             token_type: Eof,
             lexeme: "".to_string(),
@@ -98,14 +118,77 @@ impl Scanner {
         match c {
             '(' => self.add_token(LeftParen),
             ')' => self.add_token(RightParen),
-            '{' => self.add_token(LeftBrace),
-            '}' => self.add_token(RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(LeftBrace);
+            }
+            // The '}' closing a `${...}` continues the string around it
+            '}' if self.interpolations.last() == Some(&0) => {
+                self.interpolations.pop();
+                self.add_token(InterpolationEnd);
+                self.start = self.current;
+                self.string()?;
+            }
+            '}' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth -= 1;
+                }
+                self.add_token(RightBrace);
+            }
+            '[' => self.add_token(LeftBracket),
+            ']' => self.add_token(RightBracket),
             ',' => self.add_token(Comma),
+            ':' => self.add_token(Colon),
+            '?' => self.add_token(Question),
             '.' => self.add_token(Dot),
-            '-' => self.add_token(Minus),
-            '+' => self.add_token(Plus),
+            '-' => {
+                let token = if self.char_match('=') {
+                    MinusEqual
+                } else if self.char_match('-') {
+                    MinusMinus
+                } else {
+                    Minus
+                };
+                self.add_token(token);
+            }
+            '+' => {
+                let token = if self.char_match('=') {
+                    PlusEqual
+                } else if self.char_match('+') {
+                    PlusPlus
+                } else {
+                    Plus
+                };
+                self.add_token(token);
+            }
             ';' => self.add_token(Semicolon),
-            '*' => self.add_token(Star),
+            '%' => {
+                let token = if self.char_match('=') {
+                    PercentEqual
+                } else {
+                    Percent
+                };
+                self.add_token(token);
+            }
+            '*' => {
+                let token = if self.char_match('*') {
+                    StarStar
+                } else if self.char_match('=') {
+                    StarEqual
+                } else {
+                    Star
+                };
+                self.add_token(token);
+            }
+            '~' => {
+                if self.char_match('/') {
+                    self.add_token(TildeSlash);
+                } else {
+                    return Err(format!("Expected '/' after '~' at line {}", self.line));
+                }
+            }
             '!' => {
                 let token = if self.char_match('=') {
                     // !=
@@ -118,6 +201,8 @@ impl Scanner {
             '=' => {
                 let token = if self.char_match('=') {
                     EqualEqual
+                } else if self.char_match('>') {
+                    Arrow
                 } else {
                     Equal
                 };
@@ -152,20 +237,23 @@ impl Scanner {
                         }
                         self.advance();
                     }
+                } else if self.char_match('*') {
+                    self.block_comment()?;
+                } else if self.char_match('=') {
+                    self.add_token(SlashEqual);
                 } else {
                     self.add_token(Slash);
                 }
             },
             '|' => {
-                if self.char_match('>') {
-                    self.add_token(Pipe);
-                } else {
-                    return Err(format!("Expected '>' at line {}", self.line));
-                }
-            },
+                let token = if self.char_match('>') { Pipe } else { Bar };
+                self.add_token(token);
+            }
             ' ' | '\r' | '\t' => {}
             '\n' => self.line += 1,
+            '"' if self.peek() == '"' && self.peek_next() == '"' => self.triple_quoted_string()?,
             '"' => self.string()?,
+            'r' if self.peek() == '"' => self.raw_string()?,
 
             c => {
                 if is_digit(c) {
@@ -186,77 +274,276 @@ impl Scanner {
             self.advance();
         }
 
-        let substring = &self.source[self.start..self.current];
-        if let Some(&t_type) = self.keywords.get(substring) {
+        let substring = self.lexeme();
+        if let Some(&t_type) = self.keywords.get(substring.as_str()) {
             self.add_token(t_type);
         } else {
             self.add_token(Identifier);
         }
     }
 
+    // Numbers are decimal with an optional fraction and exponent, like
+    // 1_000.5e-3, or 0x, 0b and 0o prefixed integers. A '.' followed by a
+    // name is a method call, as in 3.7.floor().
     fn number(self: &mut Self) -> Result<(), String> {
-        while is_digit(self.peek()) {
-            self.advance();
-        }
-
-        if self.peek() == '.' && is_digit(self.peek_next()) {
-            self.advance();
+        self.current = self.start;
+        let radix = match (self.peek(), self.peek_next()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'b' | 'B') => 2,
+            ('0', 'o' | 'O') => 8,
+            _ => 10,
+        };
+
+        let value = if radix != 10 {
+            let prefix = format!("{}{}", self.advance(), self.advance());
+            let digits = self.digits(radix, &prefix)?;
+            u64::from_str_radix(&digits, radix).map(|x| x as f64).ok()
+        } else {
+            let mut text = self.digits(10, "")?;
+            if self.peek() == '.' && !is_alpha(self.peek_next()) {
+                self.advance();
+                text.push('.');
+                text.push_str(&self.digits(10, ".")?);
+            }
+            if matches!(self.peek(), 'e' | 'E') {
+                self.advance();
+                text.push('e');
+                if matches!(self.peek(), '+' | '-') {
+                    text.push(self.advance());
+                }
+                text.push_str(&self.digits(10, "e")?);
+            }
+            text.parse::<f64>().ok()
+        };
 
-            while is_digit(self.peek()) {
+        if is_alpha_numeric(self.peek()) {
+            let c = self.peek();
+            while is_alpha_numeric(self.peek()) {
                 self.advance();
             }
+            return Err(format!(
+                "Invalid character '{c}' in number literal at line {}",
+                self.line
+            ));
         }
-        let substring = &self.source[self.start..self.current];
-        let value = substring.parse::<f64>();
+
         match value {
-            Ok(value) => self.add_token_lit(Number, Some(FValue(value))),
-            Err(_) => return Err(format!("Could not parse number: {}", substring)),
+            Some(value) => self.add_token_lit(Number, Some(FValue(value))),
+            None => {
+                return Err(format!(
+                    "Could not parse number at line {}: {}",
+                    self.line,
+                    self.lexeme()
+                ));
+            }
         }
 
         Ok(())
     }
 
+    // Digits in the given radix, which may be separated by single '_'
+    fn digits(&mut self, radix: u32, after: &str) -> Result<String, String> {
+        let mut digits = String::new();
+        let mut underscores = String::new();
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            let c = self.advance();
+            underscores.push(c);
+            if c != '_' {
+                digits.push(c);
+            }
+        }
+
+        if digits.is_empty() {
+            return Err(format!(
+                "Expected digits after '{after}' in number literal at line {}",
+                self.line
+            ));
+        }
+        if underscores.starts_with('_') || underscores.ends_with('_') || underscores.contains("__")
+        {
+            return Err(format!(
+                "Misplaced '_' in number literal at line {}",
+                self.line
+            ));
+        }
+
+        Ok(digits)
+    }
+
     fn peek_next(self: &Self) -> char {
         if self.current + 1 >= self.source.len() {
             return '\0';
         }
 
-        self.source.chars().nth(self.current + 1).unwrap()
+        self.source[self.current + 1]
     }
 
+    // Scans up to the closing '"', or up to a `${` where an interpolated
+    // expression starts. The text before it becomes an Interpolation token,
+    // the expression is scanned as usual and its closing '}' becomes an
+    // InterpolationEnd token before this is called again for the rest of
+    // the string.
     fn string(self: &mut Self) -> Result<(), String> {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
+        let mut value = String::new();
+        // A bad escape is reported once the whole string has been scanned
+        let mut error = None;
+        loop {
+            if self.is_at_end() {
+                return Err("Unterminated string".to_string());
+            }
+
+            match self.advance() {
+                '"' => break,
+                '\\' => match self.escape() {
+                    Ok(ch) => value.push(ch),
+                    Err(msg) => error = error.or(Some(msg)),
+                },
+                '$' if self.peek() == '{' => {
+                    self.advance();
+                    self.add_token_lit(Interpolation, Some(StringValue(value)));
+                    self.interpolations.push(0);
+                    return error.map_or(Ok(()), Err);
+                }
+                c => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                }
+            }
+        }
+
+        self.add_token_lit(StringLit, Some(StringValue(value)));
+
+        error.map_or(Ok(()), Err)
+    }
+
+    // Block comments nest, so that code containing them can be commented out
+    fn block_comment(&mut self) -> Result<(), String> {
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                return Err("Unterminated block comment".to_string());
+            }
+
+            match self.advance() {
+                '\n' => self.line += 1,
+                '/' if self.char_match('*') => depth += 1,
+                '*' if self.char_match('/') => depth -= 1,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Scans the rest of a string up to `end`, keeping backslashes as they are
+    fn verbatim(&mut self, end: &str) -> Result<String, String> {
+        let mut value = String::new();
+        while !value.ends_with(end) {
+            if self.is_at_end() {
+                return Err("Unterminated string".to_string());
+            }
+
+            let c = self.advance();
+            if c == '\n' {
                 self.line += 1;
             }
+            value.push(c);
+        }
+        value.truncate(value.len() - end.len());
+
+        Ok(value)
+    }
+
+    // r"C:\path\*.txt"
+    fn raw_string(&mut self) -> Result<(), String> {
+        self.advance();
+        let value = self.verbatim("\"")?;
+        self.add_token_lit(StringLit, Some(StringValue(value)));
+
+        Ok(())
+    }
+
+    // Triple quoted strings are raw too and may contain single quotes. A line
+    // break right after the opening quotes is not part of the string.
+    fn triple_quoted_string(&mut self) -> Result<(), String> {
+        self.advance();
+        self.advance();
+        if self.peek() == '\n' {
             self.advance();
+            self.line += 1;
         }
+        let value = self.verbatim("\"\"\"")?;
+        self.add_token_lit(StringLit, Some(StringValue(value)));
+
+        Ok(())
+    }
 
+    fn escape(&mut self) -> Result<char, String> {
         if self.is_at_end() {
             return Err("Unterminated string".to_string());
         }
 
-        self.advance();
-
-        let value = &self.source[self.start + 1..self.current - 1];
+        let c = self.advance();
+        let escaped = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            'u' => {
+                // \u{1F600}, with one to six hex digits
+                if !self.char_match('{') {
+                    return Err(format!("Expected '{{' after \\u at line {}", self.line));
+                }
+                let mut digits = String::new();
+                while self.peek().is_ascii_hexdigit() && digits.len() < 6 {
+                    digits.push(self.advance());
+                }
+                if !self.char_match('}') {
+                    return Err(format!(
+                        "Expected hex digits and '}}' in \\u{{...}} at line {}",
+                        self.line
+                    ));
+                }
 
-        self.add_token_lit(StringLit, Some(StringValue(value.to_string())));
+                let code = u32::from_str_radix(&digits, 16).unwrap_or(u32::MAX);
+                match char::from_u32(code) {
+                    Some(ch) => ch,
+                    None => {
+                        return Err(format!(
+                            "Invalid unicode escape \\u{{{digits}}} at line {}",
+                            self.line
+                        ))
+                    }
+                }
+            }
+            c => {
+                return Err(format!(
+                    "Invalid escape sequence \\{c} at line {}",
+                    self.line
+                ))
+            }
+        };
 
-        Ok(())
+        Ok(escaped)
     }
 
     fn peek(self: &Self) -> char {
         if self.is_at_end() {
             return '\0';
         }
-        self.source.chars().nth(self.current).unwrap()
+        self.source[self.current]
     }
 
     fn char_match(self: &mut Self, ch: char) -> bool {
         if self.is_at_end() {
             return false;
         }
-        if self.source.chars().nth(self.current).unwrap() != ch {
+        if self.source[self.current] != ch {
             return false;
         } else {
             self.current += 1;
@@ -265,7 +552,7 @@ impl Scanner {
     }
 
     fn advance(self: &mut Self) -> char {
-        let c = self.source.chars().nth(self.current).unwrap();
+        let c = self.source[self.current];
         self.current += 1;
 
         c
@@ -275,8 +562,12 @@ impl Scanner {
         self.add_token_lit(token_type, None);
     }
 
+    fn lexeme(&self) -> String {
+        self.source[self.start..self.current].iter().collect()
+    }
+
     fn add_token_lit(self: &mut Self, token_type: TokenType, literal: Option<LiteralValue>) {
-        let text = self.source[self.start..self.current].to_string();
+        let text = self.lexeme();
 
         self.tokens.push(Token {
             token_type: token_type,
@@ -294,6 +585,11 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
+    Question,
+    Bar, // | between alternative patterns
     Comma,
     Dot,
     Minus,
@@ -301,6 +597,7 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Percent,
 
     // One Or Two Chars
     Bang,
@@ -311,22 +608,38 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
-    Pipe, // |>
-    Gets, // <-
+    Pipe,       // |>
+    Gets,       // <-
+    Arrow,      // =>
+    StarStar,   // **
+    TildeSlash, // ~/ integer division
+
+    // Compound assignment
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    PlusPlus,
+    MinusMinus,
 
     // Literals
     Identifier,
     StringLit,
+    Interpolation,    // The text of a string before a `${`
+    InterpolationEnd, // The '}' closing a `${`
     Number,
 
     // Keywords
     And,
     Class,
+    Const,
     Else,
     False,
     Fun,
     For,
     If,
+    Match,
     Nil,
     Or,
     Print,
@@ -388,6 +701,161 @@ mod tests {
         assert_eq!(scanner.tokens[6].token_type, Eof);
     }
 
+    #[test]
+    fn handle_arithmetic_operators() {
+        let source = "7 % 2 ** 3 ~/ 4 * 5";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens[1].token_type, Percent);
+        assert_eq!(scanner.tokens[3].token_type, StarStar);
+        assert_eq!(scanner.tokens[5].token_type, TildeSlash);
+        assert_eq!(scanner.tokens[7].token_type, Star);
+        assert!(Scanner::new("1 ~ 2").scan_tokens().is_err());
+    }
+
+    #[test]
+    fn handle_compound_assignment_and_arrow() {
+        let source = "a += 1 -= 2 *= 3 /= 4 %= 5 ** 6 - -7 => 8 ++ -- + +";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let types = scanner
+            .tokens
+            .iter()
+            .filter(|token| token.token_type != Number)
+            .map(|token| token.token_type)
+            .collect::<Vec<TokenType>>();
+        assert_eq!(
+            types,
+            vec![
+                Identifier,
+                PlusEqual,
+                MinusEqual,
+                StarEqual,
+                SlashEqual,
+                PercentEqual,
+                StarStar,
+                Minus,
+                Minus,
+                Arrow,
+                PlusPlus,
+                MinusMinus,
+                Plus,
+                Plus,
+                Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn handle_escape_sequences() {
+        let source = r#""a\tb\n\"c\" \\ \u{1F600} \${x}""#;
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        match scanner.tokens[0].literal.as_ref().unwrap() {
+            StringValue(s) => assert_eq!(s, "a\tb\n\"c\" \\ \u{1F600} ${x}"),
+            _ => panic!("Should be a string literal"),
+        }
+
+        let err = Scanner::new("\"a\\qb\";\nvar x;")
+            .scan_tokens()
+            .unwrap_err();
+        assert_eq!(err, "Invalid escape sequence \\q at line 1\n");
+        assert!(Scanner::new(r#""\u{110000}""#).scan_tokens().is_err());
+    }
+
+    #[test]
+    fn handle_block_comments() {
+        let source = "/* a /* nested\n */ still\n */ x /**/ y";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens.len(), 3);
+        assert_eq!(scanner.tokens[0].lexeme, "x");
+        assert_eq!(scanner.tokens[0].line_number, 3);
+        assert_eq!(scanner.tokens[1].lexeme, "y");
+
+        let err = Scanner::new("/* /* */").scan_tokens().unwrap_err();
+        assert_eq!(err, "Unterminated block comment\n");
+    }
+
+    #[test]
+    fn handle_raw_strings() {
+        let source = "r\"a\\n${b}\" \"\"\"\nsay \"hi\"\n\\t\"\"\" r";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let literals = scanner.tokens[..2]
+            .iter()
+            .map(|token| match token.literal.as_ref().unwrap() {
+                StringValue(s) => s.clone(),
+                _ => panic!("Should be a string literal"),
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(literals, vec!["a\\n${b}", "say \"hi\"\n\\t"]);
+        assert_eq!(scanner.tokens[2].token_type, Identifier);
+        assert_eq!(scanner.tokens[2].line_number, 3);
+        assert!(Scanner::new("\"\"\"open\"\"").scan_tokens().is_err());
+    }
+
+    #[test]
+    fn handle_interpolation() {
+        let source = r#""a ${b + "${c}"} d""#;
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let types = scanner
+            .tokens
+            .iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<TokenType>>();
+        assert_eq!(
+            types,
+            vec![
+                Interpolation,
+                Identifier,
+                Plus,
+                Interpolation,
+                Identifier,
+                InterpolationEnd,
+                StringLit,
+                InterpolationEnd,
+                StringLit,
+                Eof
+            ]
+        );
+        assert!(Scanner::new(r#""a ${b"#).scan_tokens().is_err());
+    }
+
+    #[test]
+    fn handle_brackets() {
+        let source = "[1, 2][0]";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let types = scanner
+            .tokens
+            .iter()
+            .map(|token| token.token_type)
+            .collect::<Vec<TokenType>>();
+        assert_eq!(
+            types,
+            vec![
+                LeftBracket,
+                Number,
+                Comma,
+                Number,
+                RightBracket,
+                LeftBracket,
+                Number,
+                RightBracket,
+                Eof
+            ]
+        );
+    }
+
     #[test]
     fn handle_two_char_tokens() {
         let source = "! != == >=";
@@ -463,6 +931,92 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rich_number_literals() {
+        let source = "0xFF 0b1010 0o755 1_000_000 1.5e-3 2E+2 3.7.floor";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let values = scanner
+            .tokens
+            .iter()
+            .filter_map(|token| match token.literal {
+                Some(FValue(val)) => Some(val),
+                _ => None,
+            })
+            .collect::<Vec<f64>>();
+        assert_eq!(
+            values,
+            vec![255.0, 10.0, 493.0, 1000000.0, 0.0015, 200.0, 3.7]
+        );
+        assert_eq!(scanner.tokens[7].token_type, Dot);
+    }
+
+    #[test]
+    fn malformed_number_literals() {
+        for source in ["1.", "0x", "0b102", "1_", "1__0", "1e", "12abc"] {
+            let result = Scanner::new(source).scan_tokens();
+            assert!(result.is_err(), "{source} should not scan");
+        }
+
+        let err = Scanner::new("\n0xG1").scan_tokens().unwrap_err();
+        assert_eq!(
+            err,
+            "Expected digits after '0x' in number literal at line 2\n"
+        );
+    }
+
+    #[test]
+    fn handle_unicode() {
+        let source = "// ünïcödé 🦀\nvar größe = \"日本\"; /* ñ */ π2 _ü";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let lexemes = scanner
+            .tokens
+            .iter()
+            .map(|token| token.lexeme.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(
+            lexemes,
+            vec!["var", "größe", "=", "\"日本\"", ";", "π2", "_ü", ""]
+        );
+        assert_eq!(scanner.tokens[1].token_type, Identifier);
+        match scanner.tokens[3].literal.as_ref() {
+            Some(StringValue(s)) => assert_eq!(s, "日本"),
+            _ => panic!("Should be a string literal"),
+        }
+        assert_eq!(scanner.tokens[6].line_number, 2);
+
+        let err = Scanner::new("a ¤ b").scan_tokens().unwrap_err();
+        assert_eq!(err, "Unrecognized char at line 1: ¤\n");
+        assert!(Scanner::new("١٢").scan_tokens().is_err());
+    }
+
+    #[test]
+    fn identifiers_with_combining_marks() {
+        // "café" in decomposed form, with U+0301 COMBINING ACUTE ACCENT
+        let source = "var cafe\u{301} = 1;";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens[1].token_type, Identifier);
+        assert_eq!(scanner.tokens[1].lexeme, "cafe\u{301}");
+        assert_eq!(scanner.tokens.len(), 6);
+
+        // A mark can continue a name but not start one
+        let err = Scanner::new("\u{301}x").scan_tokens().unwrap_err();
+        assert_eq!(err, "Unrecognized char at line 1: \u{301}\n");
+    }
+
+    #[test]
+    fn identifiers_reject_number_symbols() {
+        for (source, symbol) in [("a²", '²'), ("x½", '½'), ("n¹", '¹')] {
+            let err = Scanner::new(source).scan_tokens().unwrap_err();
+            assert_eq!(err, format!("Unrecognized char at line 1: {symbol}\n"));
+        }
+    }
+
     #[test]
     fn get_identifer() {
         let source = "this_is_a_var = 12;";
@@ -501,6 +1055,24 @@ mod tests {
         assert_eq!(scanner.tokens[12].token_type, Eof);
     }
 
+    #[test]
+    fn skips_shebang_line() {
+        let source = "#!/usr/bin/env dena\nprint 1;";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens.len(), 4);
+        assert_eq!(scanner.tokens[0].token_type, Print);
+        assert_eq!(scanner.tokens[0].line_number, 2);
+    }
+
+    #[test]
+    fn shebang_only_on_first_line() {
+        let source = "print 1;\n#!/usr/bin/env dena";
+        let mut scanner = Scanner::new(source);
+        assert!(scanner.scan_tokens().is_err());
+    }
+
     #[test]
     fn gets_keyword() {
         let source = "fun cmd <- \"echo hello\";";
@@ -0,0 +1,365 @@
+use crate::expr::LiteralValue;
+use crate::stdlib::string_arg;
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+thread_local! {
+    // Why the last file function that can fail did so, for `ioError()`
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// File functions don't stop the script when the file system refuses them.
+// They return nil instead and keep the reason for `ioError()`, until the
+// next call of one of them. Wrong argument types are still errors.
+fn io_result<T>(
+    fun: &str,
+    path: &str,
+    result: io::Result<T>,
+    to_value: impl FnOnce(T) -> LiteralValue,
+) -> Result<LiteralValue, String> {
+    match result {
+        Ok(value) => {
+            LAST_ERROR.with(|error| error.replace(None));
+            Ok(to_value(value))
+        }
+        Err(e) => {
+            let msg = format!("{fun} failed for '{path}': {e}");
+            LAST_ERROR.with(|error| error.replace(Some(msg)));
+            Ok(LiteralValue::Nil)
+        }
+    }
+}
+
+// Functions that change files return true when they succeed
+fn done(_: ()) -> LiteralValue {
+    LiteralValue::True
+}
+
+pub fn io_error_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    Ok(LAST_ERROR.with(|error| match &*error.borrow() {
+        Some(msg) => LiteralValue::StringValue(msg.clone()),
+        None => LiteralValue::Nil,
+    }))
+}
+
+pub fn read_file_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "readFile")?;
+    io_result(
+        "readFile",
+        path,
+        fs::read_to_string(path),
+        LiteralValue::StringValue,
+    )
+}
+
+pub fn write_file_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "writeFile")?;
+    let contents = string_arg(args, 1, "writeFile")?;
+    io_result("writeFile", path, fs::write(path, contents), done)
+}
+
+pub fn append_file_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "appendFile")?;
+    let contents = string_arg(args, 1, "appendFile")?;
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    io_result("appendFile", path, result, done)
+}
+
+pub fn exists_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "exists")?;
+    Ok(LiteralValue::from_bool(Path::new(path).exists()))
+}
+
+pub fn is_file_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "isFile")?;
+    Ok(LiteralValue::from_bool(Path::new(path).is_file()))
+}
+
+pub fn is_dir_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "isDir")?;
+    Ok(LiteralValue::from_bool(Path::new(path).is_dir()))
+}
+
+pub fn file_size_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "fileSize")?;
+    io_result("fileSize", path, fs::metadata(path), |metadata| {
+        LiteralValue::Number(metadata.len() as f64)
+    })
+}
+
+// Seconds since the Unix epoch, like `clock`
+pub fn modified_time_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "modifiedTime")?;
+    let since_epoch = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .and_then(|modified| {
+            modified
+                .duration_since(UNIX_EPOCH)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        });
+
+    io_result("modifiedTime", path, since_epoch, |since_epoch| {
+        LiteralValue::Number(since_epoch.as_millis() as f64 / 1000.0)
+    })
+}
+
+pub fn list_dir_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "listDir")?;
+    let names = fs::read_dir(path).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .collect::<io::Result<Vec<String>>>()
+    });
+
+    io_result("listDir", path, names, |mut names| {
+        names.sort();
+        LiteralValue::from_list(names.into_iter().map(LiteralValue::StringValue).collect())
+    })
+}
+
+// Creates the missing parent directories too, like `mkdir -p`
+pub fn mkdir_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "mkdir")?;
+    io_result("mkdir", path, fs::create_dir_all(path), done)
+}
+
+// Removes a file or an empty directory
+pub fn remove_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "remove")?;
+    let result = if Path::new(path).is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    io_result("remove", path, result, done)
+}
+
+pub fn rename_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let from = string_arg(args, 0, "rename")?;
+    let to = string_arg(args, 1, "rename")?;
+    io_result("rename", from, fs::rename(from, to), done)
+}
+
+// Matches a single path component against a pattern with `*`, `?` and
+// `[...]` character classes (negated with `!` or `^`)
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| wildcard_match(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && wildcard_match(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let end = match pattern.iter().skip(2).position(|c| *c == ']') {
+                Some(end) => end + 2,
+                None => {
+                    return name.first() == Some(&'[') && wildcard_match(&pattern[1..], &name[1..])
+                }
+            };
+            let ch = match name.first() {
+                Some(ch) => *ch,
+                None => return false,
+            };
+
+            let mut class = &pattern[1..end];
+            let negated = matches!(class.first(), Some('!') | Some('^'));
+            if negated {
+                class = &class[1..];
+            }
+            let mut found = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    found |= class[i] <= ch && ch <= class[i + 2];
+                    i += 3;
+                } else {
+                    found |= class[i] == ch;
+                    i += 1;
+                }
+            }
+
+            found != negated && wildcard_match(&pattern[end + 1..], &name[1..])
+        }
+        Some(c) => name.first() == Some(c) && wildcard_match(&pattern[1..], &name[1..]),
+    }
+}
+
+fn has_wildcard(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+fn sorted_entries(dir: &Path) -> Vec<(String, PathBuf)> {
+    let read_from = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let mut entries = match fs::read_dir(read_from) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let path = dir.join(&name);
+                (name, path)
+            })
+            .collect::<Vec<(String, PathBuf)>>(),
+        Err(_) => vec![],
+    };
+    entries.sort();
+
+    entries
+}
+
+fn glob_walk(base: &Path, components: &[&str], matches: &mut Vec<String>) {
+    let component = match components.first() {
+        Some(component) => *component,
+        None => {
+            if !base.as_os_str().is_empty() {
+                matches.push(base.display().to_string());
+            }
+            return;
+        }
+    };
+
+    if component == "**" {
+        // `**` matches zero or more directories. It does not follow
+        // symlinks, which could lead back into a directory being walked.
+        glob_walk(base, &components[1..], matches);
+        for (name, path) in sorted_entries(base) {
+            if !name.starts_with('.') && path.is_dir() && !path.is_symlink() {
+                glob_walk(&path, components, matches);
+            }
+        }
+    } else if has_wildcard(component) {
+        let pattern = component.chars().collect::<Vec<char>>();
+        for (name, path) in sorted_entries(base) {
+            // Like in the shell, wildcards don't match hidden files
+            if name.starts_with('.') && !component.starts_with('.') {
+                continue;
+            }
+            if wildcard_match(&pattern, &name.chars().collect::<Vec<char>>()) {
+                glob_walk(&path, &components[1..], matches);
+            }
+        }
+    } else {
+        let path = base.join(component);
+        if path.exists() {
+            glob_walk(&path, &components[1..], matches);
+        }
+    }
+}
+
+pub fn glob(pattern: &str) -> Vec<String> {
+    let base = if pattern.starts_with('/') {
+        PathBuf::from("/")
+    } else {
+        PathBuf::new()
+    };
+    let components = pattern
+        .split('/')
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<&str>>();
+
+    let mut matches = vec![];
+    glob_walk(&base, &components, &mut matches);
+    matches.sort();
+    matches.dedup();
+
+    matches
+}
+
+pub fn glob_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let pattern = string_arg(args, 0, "glob")?;
+    Ok(LiteralValue::from_list(
+        glob(pattern)
+            .into_iter()
+            .map(LiteralValue::StringValue)
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern = pattern.chars().collect::<Vec<char>>();
+        let name = name.chars().collect::<Vec<char>>();
+        wildcard_match(&pattern, &name)
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(matches("*.dena", "while.dena"));
+        assert!(!matches("*.dena", "while.rs"));
+        assert!(matches("fun?ef.dena", "fundef.dena"));
+        assert!(matches("resolve[1-3].dena", "resolve2.dena"));
+        assert!(!matches("resolve[!1-3].dena", "resolve2.dena"));
+        assert!(matches("*", ""));
+        assert!(!matches("?", ""));
+    }
+
+    #[test]
+    fn glob_case_files() {
+        let found = glob("src/tests/cases/resolve[12].dena");
+        assert_eq!(
+            found,
+            vec![
+                "src/tests/cases/resolve1.dena",
+                "src/tests/cases/resolve2.dena"
+            ]
+        );
+
+        let found = glob("src/**/mod.rs");
+        assert!(found.contains(&"src/tests/mod.rs".to_string()));
+        assert!(found.contains(&"src/stdlib/mod.rs".to_string()));
+    }
+
+    #[test]
+    fn failures_are_values() {
+        let missing = LiteralValue::StringValue("target/no_such_dir/file.txt".to_string());
+        assert_eq!(
+            read_file_impl(std::slice::from_ref(&missing)),
+            Ok(LiteralValue::Nil)
+        );
+        match io_error_impl(&[]) {
+            Ok(LiteralValue::StringValue(msg)) => {
+                assert!(msg.starts_with("readFile failed for 'target/no_such_dir/file.txt': "))
+            }
+            other => panic!("Expected an error message but got {:?}", other),
+        }
+
+        assert_eq!(is_file_impl(&[missing]), Ok(LiteralValue::False));
+        assert!(matches!(
+            io_error_impl(&[]),
+            Ok(LiteralValue::StringValue(_))
+        ));
+
+        let dir = LiteralValue::StringValue("src".to_string());
+        assert!(matches!(list_dir_impl(&[dir]), Ok(LiteralValue::List(_))));
+        assert_eq!(io_error_impl(&[]), Ok(LiteralValue::Nil));
+
+        assert!(read_file_impl(&[LiteralValue::Number(1.0)]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn glob_skips_symlink_loops() {
+        let dir = Path::new("target/glob_symlink_test");
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/found.txt"), "").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("sub/loop")).unwrap();
+
+        assert_eq!(
+            glob("target/glob_symlink_test/**/*.txt"),
+            vec!["target/glob_symlink_test/sub/found.txt"]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
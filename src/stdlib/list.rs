@@ -0,0 +1,62 @@
+use crate::expr::{call_value, LiteralValue};
+use crate::stdlib::list_arg;
+
+// Appends in place and returns the list, so that calls can be chained
+pub fn push_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "push")?;
+    elements.borrow_mut().push(args[1].clone());
+    Ok(args[0].clone())
+}
+
+// Removes and returns the last element, nil when the list is empty
+pub fn pop_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "pop")?;
+    let last = elements.borrow_mut().pop();
+    Ok(last.unwrap_or(LiteralValue::Nil))
+}
+
+pub fn reverse_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "reverse")?;
+    let reversed = elements.borrow().iter().rev().cloned().collect();
+    Ok(LiteralValue::from_list(reversed))
+}
+
+pub fn map_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "map")?;
+    // Copied so that the function may change the list while we go
+    let elements = elements.borrow().clone();
+
+    let mut mapped = vec![];
+    for element in elements {
+        mapped.push(call_value(&args[1], vec![element])?);
+    }
+
+    Ok(LiteralValue::from_list(mapped))
+}
+
+pub fn filter_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "filter")?;
+    let elements = elements.borrow().clone();
+
+    let mut kept = vec![];
+    for element in elements {
+        if call_value(&args[1], vec![element.clone()])?.is_truthy() == LiteralValue::True {
+            kept.push(element);
+        }
+    }
+
+    Ok(LiteralValue::from_list(kept))
+}
+
+// reduce([1, 2, 3], fun (acc, x) { return acc + x; }, 0) is 6
+pub fn reduce_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "reduce")?;
+    let elements = elements.borrow().clone();
+
+    let mut acc = args[2].clone();
+    for element in elements {
+        acc = call_value(&args[1], vec![acc, element])?;
+    }
+
+    Ok(acc)
+}
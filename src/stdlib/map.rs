@@ -0,0 +1,54 @@
+use crate::expr::LiteralValue;
+use crate::stdlib::string_arg;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Entries = Rc<RefCell<Vec<(String, LiteralValue)>>>;
+
+fn map_arg(args: &[LiteralValue], fun: &str) -> Result<Entries, String> {
+    match &args[0] {
+        LiteralValue::Map(entries) => Ok(entries.clone()),
+        other => Err(format!(
+            "{fun} expected a Map as argument 1 but got {}",
+            other.to_type()
+        )),
+    }
+}
+
+pub fn keys_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let entries = map_arg(args, "keys")?;
+    let keys = entries
+        .borrow()
+        .iter()
+        .map(|(key, _)| LiteralValue::StringValue(key.clone()))
+        .collect();
+    Ok(LiteralValue::from_list(keys))
+}
+
+pub fn values_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let entries = map_arg(args, "values")?;
+    let values = entries
+        .borrow()
+        .iter()
+        .map(|(_, value)| value.clone())
+        .collect();
+    Ok(LiteralValue::from_list(values))
+}
+
+pub fn has_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let entries = map_arg(args, "has")?;
+    let key = string_arg(args, 1, "has")?;
+    let found = entries.borrow().iter().any(|(k, _)| k == key);
+    Ok(LiteralValue::from_bool(found))
+}
+
+// Removes the entry in place and returns its value, nil when there was none
+pub fn delete_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let entries = map_arg(args, "delete")?;
+    let key = string_arg(args, 1, "delete")?;
+    let mut entries = entries.borrow_mut();
+    match entries.iter().position(|(k, _)| k == key) {
+        Some(idx) => Ok(entries.remove(idx).1),
+        None => Ok(LiteralValue::Nil),
+    }
+}
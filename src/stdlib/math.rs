@@ -0,0 +1,68 @@
+use crate::expr::LiteralValue;
+use crate::stdlib::number_arg;
+
+pub fn floor_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "floor")?;
+    Ok(LiteralValue::Number(x.floor()))
+}
+
+pub fn ceil_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "ceil")?;
+    Ok(LiteralValue::Number(x.ceil()))
+}
+
+// Rounds half way cases away from zero
+pub fn round_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "round")?;
+    Ok(LiteralValue::Number(x.round()))
+}
+
+pub fn abs_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "abs")?;
+    Ok(LiteralValue::Number(x.abs()))
+}
+
+pub fn sqrt_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "sqrt")?;
+    Ok(LiteralValue::Number(x.sqrt()))
+}
+
+pub fn pow_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "pow")?;
+    let y = number_arg(args, 1, "pow")?;
+    Ok(LiteralValue::Number(x.powf(y)))
+}
+
+pub fn min_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "min")?;
+    let y = number_arg(args, 1, "min")?;
+    Ok(LiteralValue::Number(x.min(y)))
+}
+
+pub fn max_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "max")?;
+    let y = number_arg(args, 1, "max")?;
+    Ok(LiteralValue::Number(x.max(y)))
+}
+
+// Angles are in radians
+pub fn sin_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "sin")?;
+    Ok(LiteralValue::Number(x.sin()))
+}
+
+pub fn cos_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "cos")?;
+    Ok(LiteralValue::Number(x.cos()))
+}
+
+pub fn tan_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "tan")?;
+    Ok(LiteralValue::Number(x.tan()))
+}
+
+// The natural logarithm
+pub fn log_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "log")?;
+    Ok(LiteralValue::Number(x.ln()))
+}
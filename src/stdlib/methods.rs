@@ -0,0 +1,107 @@
+// Methods of the built-in types are the natives of the standard library with
+// the receiver as first argument, so `s.upper()` is the same as `upper(s)`
+// and `s |> upper`.
+use crate::expr::{CallableImpl, LiteralValue, NativeFunctionImpl};
+use crate::stdlib::{list, map, math, number, string, NativeFn};
+use std::rc::Rc;
+
+// The arity counts the receiver
+type Method = (&'static str, usize, NativeFn);
+
+const STRING_METHODS: &[Method] = &[
+    ("len", 1, string::len_impl),
+    ("split", 2, string::split_impl),
+    ("trim", 1, string::trim_impl),
+    ("upper", 1, string::upper_impl),
+    ("lower", 1, string::lower_impl),
+    ("contains", 2, string::contains_impl),
+    ("startsWith", 2, string::starts_with_impl),
+    ("endsWith", 2, string::ends_with_impl),
+    ("replace", 3, string::replace_impl),
+    ("indexOf", 2, string::index_of_impl),
+    ("substring", 3, string::substring_impl),
+    ("repeat", 2, string::repeat_impl),
+    ("lines", 1, string::lines_impl),
+    ("chars", 1, string::chars_impl),
+    ("toNumber", 1, number::to_number_impl),
+];
+
+const NUMBER_METHODS: &[Method] = &[
+    ("floor", 1, math::floor_impl),
+    ("ceil", 1, math::ceil_impl),
+    ("round", 1, math::round_impl),
+    ("abs", 1, math::abs_impl),
+    ("sqrt", 1, math::sqrt_impl),
+    ("pow", 2, math::pow_impl),
+    ("min", 2, math::min_impl),
+    ("max", 2, math::max_impl),
+    ("toString", 1, number::to_string_impl),
+    ("format", 2, number::format_impl),
+    ("toHex", 1, number::to_hex_impl),
+    ("toBinary", 1, number::to_binary_impl),
+];
+
+const LIST_METHODS: &[Method] = &[
+    ("len", 1, string::len_impl),
+    ("contains", 2, string::contains_impl),
+    ("join", 2, string::join_impl),
+    ("push", 2, list::push_impl),
+    ("pop", 1, list::pop_impl),
+    ("reverse", 1, list::reverse_impl),
+    ("map", 2, list::map_impl),
+    ("filter", 2, list::filter_impl),
+    ("reduce", 3, list::reduce_impl),
+];
+
+const MAP_METHODS: &[Method] = &[
+    ("len", 1, string::len_impl),
+    ("keys", 1, map::keys_impl),
+    ("values", 1, map::values_impl),
+    ("has", 2, map::has_impl),
+    ("delete", 2, map::delete_impl),
+];
+
+/// Looks up `name` in the methods of the type of `receiver` and returns it
+/// bound to the receiver, or None when the type has no such method.
+pub fn bind_method(receiver: &LiteralValue, name: &str) -> Option<LiteralValue> {
+    let methods = match receiver {
+        LiteralValue::StringValue(_) => STRING_METHODS,
+        LiteralValue::Number(_) => NUMBER_METHODS,
+        LiteralValue::List(_) => LIST_METHODS,
+        LiteralValue::Map(_) => MAP_METHODS,
+        _ => return None,
+    };
+    let (name, arity, fun) = *methods.iter().find(|(method, _, _)| *method == name)?;
+
+    let receiver = receiver.clone();
+    let bound = NativeFunctionImpl {
+        name: name.to_string(),
+        arity: arity - 1,
+        fun: Rc::new(move |args: &[LiteralValue]| {
+            let mut all = vec![receiver.clone()];
+            all.extend_from_slice(args);
+            fun(&all)
+        }),
+    };
+
+    Some(LiteralValue::Callable(CallableImpl::NativeFunction(bound)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expr::call_value;
+
+    #[test]
+    fn bound_methods() {
+        let s = LiteralValue::StringValue("a,b".to_string());
+        let split = bind_method(&s, "split").unwrap();
+        assert_eq!(split.to_string(), "split/1");
+
+        let parts = call_value(&split, vec![LiteralValue::StringValue(",".to_string())]);
+        assert_eq!(parts.unwrap().to_string(), r#"["a", "b"]"#);
+
+        assert!(bind_method(&s, "push").is_none());
+        assert!(bind_method(&LiteralValue::Nil, "len").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+// Native functions of the standard library, registered as globals in
+// `get_globals`. Errors are reported with the line of the call.
+use crate::expr::LiteralValue;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub mod fs;
+pub mod list;
+pub mod map;
+pub mod math;
+pub mod methods;
+pub mod number;
+pub mod process;
+pub mod string;
+
+pub type NativeFn = fn(&[LiteralValue]) -> Result<LiteralValue, String>;
+
+pub fn string_arg<'a>(args: &'a [LiteralValue], idx: usize, fun: &str) -> Result<&'a str, String> {
+    match &args[idx] {
+        LiteralValue::StringValue(s) => Ok(s),
+        other => Err(format!(
+            "{fun} expected a String as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+    }
+}
+
+pub fn number_arg(args: &[LiteralValue], idx: usize, fun: &str) -> Result<f64, String> {
+    match &args[idx] {
+        LiteralValue::Number(x) => Ok(*x),
+        other => Err(format!(
+            "{fun} expected a Number as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+    }
+}
+
+pub fn list_arg(
+    args: &[LiteralValue],
+    idx: usize,
+    fun: &str,
+) -> Result<Rc<RefCell<Vec<LiteralValue>>>, String> {
+    match &args[idx] {
+        LiteralValue::List(elements) => Ok(elements.clone()),
+        other => Err(format!(
+            "{fun} expected a List as argument {} but got {}",
+            idx + 1,
+            other.to_type()
+        )),
+    }
+}
+
+// A whole, non negative Number such as a count or an index
+pub fn count_arg(args: &[LiteralValue], idx: usize, fun: &str) -> Result<usize, String> {
+    let x = number_arg(args, idx, fun)?;
+    if x.fract() != 0.0 || x < 0.0 {
+        return Err(format!(
+            "{fun} expected a whole non negative Number as argument {} but got {x}",
+            idx + 1
+        ));
+    }
+
+    Ok(x as usize)
+}
@@ -0,0 +1,237 @@
+use crate::expr::LiteralValue;
+use crate::stdlib::string::display;
+use crate::stdlib::{count_arg, number_arg};
+
+// Integers that a f64 holds exactly are printed with all their digits
+const MAX_EXACT_INTEGER: f64 = 9007199254740992.0;
+
+/// How numbers are printed: integers without a fraction, very large and very
+/// small numbers in scientific notation, and NaN and Infinity by name.
+pub fn format_number(x: f64) -> String {
+    if x.is_nan() {
+        return "NaN".to_string();
+    }
+    if x.is_infinite() {
+        return if x > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    if x == 0.0 {
+        // Also for -0
+        return "0".to_string();
+    }
+
+    let abs = x.abs();
+    if (x.fract() == 0.0 && abs > MAX_EXACT_INTEGER) || abs < 1e-6 {
+        format!("{x:e}")
+    } else {
+        x.to_string()
+    }
+}
+
+// Removes the '_' digit separators the scanner allows, which must stand
+// between two digits of the radix
+fn strip_separators(s: &str, radix: u32) -> Option<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut stripped = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '_' {
+            stripped.push(c);
+            continue;
+        }
+        let between_digits = i > 0
+            && chars[i - 1].is_digit(radix)
+            && chars.get(i + 1).is_some_and(|next| next.is_digit(radix));
+        if !between_digits {
+            return None;
+        }
+    }
+
+    Some(stripped)
+}
+
+// Accepts decimal numbers with an optional exponent and 0x, 0b and 0o
+// prefixed integers, all with an optional sign and '_' separators
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let radix = match unsigned.get(..2) {
+        Some("0x") | Some("0X") => 16,
+        Some("0b") | Some("0B") => 2,
+        Some("0o") | Some("0O") => 8,
+        _ => 10,
+    };
+    let value = if radix == 10 {
+        let digits = strip_separators(unsigned, 10)?;
+        // Rust also parses "inf" and "NaN", which are not numbers to us
+        if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            return None;
+        }
+        digits.parse::<f64>().ok()?
+    } else {
+        let digits = strip_separators(&unsigned[2..], radix)?;
+        // from_str_radix would take another sign after the prefix
+        if !digits.starts_with(|c: char| c.is_digit(radix)) {
+            return None;
+        }
+        i64::from_str_radix(&digits, radix).ok()? as f64
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+pub fn to_number_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::Number(x) => Ok(LiteralValue::Number(*x)),
+        LiteralValue::StringValue(s) => match parse_number(s) {
+            Some(x) => Ok(LiteralValue::Number(x)),
+            None => Err(format!("toNumber could not convert \"{s}\" to a Number")),
+        },
+        other => Err(format!(
+            "toNumber expected a String or a Number as argument 1 but got {}",
+            other.to_type()
+        )),
+    }
+}
+
+pub fn to_string_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::StringValue(display(&args[0])))
+}
+
+// A fixed number of digits after the decimal point
+pub fn format_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = number_arg(args, 0, "format")?;
+    let precision = count_arg(args, 1, "format")?;
+    if !x.is_finite() {
+        return Ok(LiteralValue::StringValue(format_number(x)));
+    }
+
+    Ok(LiteralValue::StringValue(round_fixed(x, precision)))
+}
+
+// Rounds half away from zero, where Rust's formatting rounds half to even
+fn round_fixed(x: f64, precision: usize) -> String {
+    // Every f64 has an exact decimal expansion with at most 1074 fraction
+    // digits, so the first dropped digit decides the rounding
+    let exact = format!("{:.*}", precision.max(1074) + 1, x.abs());
+    let point = exact.find('.').unwrap_or(exact.len());
+    let mut digits: Vec<u8> = exact.bytes().filter(|&b| b != b'.').collect();
+    let keep = point + precision;
+    let round_up = digits[keep] >= b'5';
+    digits.truncate(keep);
+
+    if round_up {
+        let mut i = keep;
+        loop {
+            if i == 0 {
+                digits.insert(0, b'1');
+                break;
+            }
+            i -= 1;
+            if digits[i] == b'9' {
+                digits[i] = b'0';
+            } else {
+                digits[i] += 1;
+                break;
+            }
+        }
+    }
+
+    let digits = String::from_utf8(digits).unwrap();
+    let (integer, fraction) = digits.split_at(digits.len() - precision);
+    let sign = if x.is_sign_negative() { "-" } else { "" };
+    if precision == 0 {
+        format!("{sign}{integer}")
+    } else {
+        format!("{sign}{integer}.{fraction}")
+    }
+}
+
+fn integer_arg(args: &[LiteralValue], fun: &str) -> Result<i64, String> {
+    let x = number_arg(args, 0, fun)?;
+    if x.fract() != 0.0 || x.abs() > MAX_EXACT_INTEGER {
+        return Err(format!(
+            "{fun} expected an integer but got {}",
+            format_number(x)
+        ));
+    }
+
+    Ok(x as i64)
+}
+
+fn format_radix(x: i64, digits: String) -> LiteralValue {
+    let sign = if x < 0 { "-" } else { "" };
+    LiteralValue::StringValue(format!("{sign}{digits}"))
+}
+
+pub fn to_hex_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = integer_arg(args, "toHex")?;
+    Ok(format_radix(x, format!("{:x}", x.unsigned_abs())))
+}
+
+pub fn to_binary_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let x = integer_arg(args, "toBinary")?;
+    Ok(format_radix(x, format!("{:b}", x.unsigned_abs())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_numbers() {
+        assert_eq!(format_number(42.0), "42");
+        assert_eq!(format_number(-0.0), "0");
+        assert_eq!(format_number(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format_number(9007199254740992.0), "9007199254740992");
+        assert_eq!(format_number(1e21), "1e21");
+        assert_eq!(format_number(1.5e-7), "1.5e-7");
+        assert_eq!(format_number(f64::NAN), "NaN");
+        assert_eq!(format_number(f64::NEG_INFINITY), "-Infinity");
+    }
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_number(" 42\n"), Some(42.0));
+        assert_eq!(parse_number("-1.5e3"), Some(-1500.0));
+        assert_eq!(parse_number("0xff"), Some(255.0));
+        assert_eq!(parse_number("-0b101"), Some(-5.0));
+        assert_eq!(parse_number("0o755"), Some(493.0));
+        assert_eq!(parse_number("inf"), None);
+        assert_eq!(parse_number("NaN"), None);
+        assert_eq!(parse_number("12abc"), None);
+        assert_eq!(parse_number(""), None);
+        assert_eq!(parse_number("0x-5"), None);
+        assert_eq!(parse_number("0b+1"), None);
+    }
+
+    #[test]
+    fn parse_separators() {
+        assert_eq!(parse_number("1_000_000"), Some(1000000.0));
+        assert_eq!(parse_number("-1_000.000_5e1_0"), Some(-1000.0005e10));
+        assert_eq!(parse_number("0xff_ff"), Some(65535.0));
+        assert_eq!(parse_number("0b1010_0101"), Some(165.0));
+        assert_eq!(parse_number("_1"), None);
+        assert_eq!(parse_number("1_"), None);
+        assert_eq!(parse_number("1__0"), None);
+        assert_eq!(parse_number("1_.5"), None);
+        assert_eq!(parse_number("0x_ff"), None);
+    }
+
+    #[test]
+    fn format_rounds_half_away_from_zero() {
+        let format = |x: f64, precision: f64| {
+            format_impl(&[LiteralValue::Number(x), LiteralValue::Number(precision)]).unwrap()
+        };
+        let string = |s: &str| LiteralValue::StringValue(s.to_string());
+        assert_eq!(format(2.5, 0.0), string("3"));
+        assert_eq!(format(-2.5, 0.0), string("-3"));
+        assert_eq!(format(0.125, 2.0), string("0.13"));
+        assert_eq!(format(9.995, 2.0), string("9.99"));
+        assert_eq!(format(99.96, 1.0), string("100.0"));
+        assert_eq!(format(1.23456, 3.0), string("1.235"));
+        assert_eq!(format(0.4, 0.0), string("0"));
+    }
+}
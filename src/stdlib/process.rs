@@ -0,0 +1,143 @@
+use crate::expr::LiteralValue;
+use crate::stdlib::{number_arg, string_arg};
+use std::cell::Cell;
+use std::env;
+
+thread_local! {
+    // Set by `exit`, which then unwinds the interpreter with an error. The
+    // caller of the interpreter checks it to end the process cleanly.
+    static EXIT_CODE: Cell<Option<i32>> = const { Cell::new(None) };
+}
+
+pub fn exit_requested() -> bool {
+    EXIT_CODE.with(|code| code.get().is_some())
+}
+
+pub fn take_exit_code() -> Option<i32> {
+    EXIT_CODE.with(|code| code.take())
+}
+
+pub fn exit_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let code = number_arg(args, 0, "exit")?;
+    if code.fract() != 0.0 || code < i32::MIN as f64 || code > i32::MAX as f64 {
+        return Err(format!("exit expected an integer exit code but got {code}"));
+    }
+
+    EXIT_CODE.with(|exit_code| exit_code.set(Some(code as i32)));
+    Err(format!("exit({code})"))
+}
+
+pub fn env_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let name = string_arg(args, 0, "env")?;
+    match env::var(name) {
+        Ok(value) => Ok(LiteralValue::StringValue(value)),
+        Err(_) => Ok(LiteralValue::Nil),
+    }
+}
+
+pub fn set_env_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let name = string_arg(args, 0, "setEnv")?;
+    let value = string_arg(args, 1, "setEnv")?;
+    if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+        return Err(format!("setEnv got an invalid variable name '{name}'"));
+    }
+
+    env::set_var(name, value);
+    Ok(LiteralValue::Nil)
+}
+
+pub fn cwd_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    match env::current_dir() {
+        Ok(path) => Ok(LiteralValue::StringValue(path.display().to_string())),
+        Err(e) => Err(format!("cwd failed: {e}")),
+    }
+}
+
+pub fn chdir_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let path = string_arg(args, 0, "chdir")?;
+    match env::set_current_dir(path) {
+        Ok(_) => Ok(LiteralValue::Nil),
+        Err(e) => Err(format!("chdir to '{path}' failed: {e}")),
+    }
+}
+
+pub fn pid_impl(_args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    Ok(LiteralValue::Number(std::process::id() as f64))
+}
+
+/// Splits the body of a command function into the program and its
+/// arguments. Words are separated by whitespace; single quotes keep their
+/// content as it is, double quotes group words and a backslash outside of
+/// single quotes takes the next char literally. No shell is involved.
+pub fn split_command(cmd: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = cmd.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(format!("Unterminated ' in command: {cmd}")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => word.extend(chars.next()),
+                        Some(c) => word.push(c),
+                        None => return Err(format!("Unterminated \" in command: {cmd}")),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.extend(chars.next());
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    if words.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_command_words() {
+        assert_eq!(
+            split_command("printenv  DENA_TEST_VAR\n").unwrap(),
+            vec!["printenv", "DENA_TEST_VAR"]
+        );
+        assert_eq!(
+            split_command(r#"awk '{ print "$1" }' "two words" a\ b ''"#).unwrap(),
+            vec!["awk", r#"{ print "$1" }"#, "two words", "a b", ""]
+        );
+        assert!(split_command("echo 'open").is_err());
+        assert!(split_command("  ").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+// String functions work on chars rather than bytes, so lengths and indexes
+// are the same for "abc" and "äöü".
+use crate::expr::LiteralValue;
+use crate::stdlib::{count_arg, list_arg, string_arg};
+
+fn strings_to_list<'a>(strings: impl Iterator<Item = &'a str>) -> LiteralValue {
+    LiteralValue::from_list(
+        strings
+            .map(|s| LiteralValue::StringValue(s.to_string()))
+            .collect(),
+    )
+}
+
+// Strings are used as they are, other values as they are printed
+pub fn display(value: &LiteralValue) -> String {
+    match value {
+        LiteralValue::StringValue(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn len_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::StringValue(s) => Ok(LiteralValue::Number(s.chars().count() as f64)),
+        LiteralValue::List(elements) => Ok(LiteralValue::Number(elements.borrow().len() as f64)),
+        LiteralValue::Map(entries) => Ok(LiteralValue::Number(entries.borrow().len() as f64)),
+        other => Err(format!(
+            "len expected a String, a List or a Map as argument 1 but got {}",
+            other.to_type()
+        )),
+    }
+}
+
+pub fn split_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "split")?;
+    let separator = string_arg(args, 1, "split")?;
+    if separator.is_empty() {
+        return chars_impl(args);
+    }
+
+    Ok(strings_to_list(s.split(separator)))
+}
+
+pub fn join_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let elements = list_arg(args, 0, "join")?;
+    let separator = string_arg(args, 1, "join")?;
+    let joined = elements
+        .borrow()
+        .iter()
+        .map(display)
+        .collect::<Vec<String>>()
+        .join(separator);
+
+    Ok(LiteralValue::StringValue(joined))
+}
+
+pub fn trim_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "trim")?;
+    Ok(LiteralValue::StringValue(s.trim().to_string()))
+}
+
+pub fn upper_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "upper")?;
+    Ok(LiteralValue::StringValue(s.to_uppercase()))
+}
+
+pub fn lower_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "lower")?;
+    Ok(LiteralValue::StringValue(s.to_lowercase()))
+}
+
+// Looks for a substring in a String or for an element in a List
+pub fn contains_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::StringValue(s) => {
+            let needle = string_arg(args, 1, "contains")?;
+            Ok(LiteralValue::from_bool(s.contains(needle)))
+        }
+        LiteralValue::List(elements) => Ok(LiteralValue::from_bool(
+            elements.borrow().contains(&args[1]),
+        )),
+        other => Err(format!(
+            "contains expected a String or a List as argument 1 but got {}",
+            other.to_type()
+        )),
+    }
+}
+
+pub fn starts_with_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "startsWith")?;
+    let prefix = string_arg(args, 1, "startsWith")?;
+    Ok(LiteralValue::from_bool(s.starts_with(prefix)))
+}
+
+pub fn ends_with_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "endsWith")?;
+    let suffix = string_arg(args, 1, "endsWith")?;
+    Ok(LiteralValue::from_bool(s.ends_with(suffix)))
+}
+
+// Replaces every occurrence
+pub fn replace_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "replace")?;
+    let from = string_arg(args, 1, "replace")?;
+    let to = string_arg(args, 2, "replace")?;
+    Ok(LiteralValue::StringValue(s.replace(from, to)))
+}
+
+// The char index of the first occurrence, -1 when there is none
+pub fn index_of_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "indexOf")?;
+    let needle = string_arg(args, 1, "indexOf")?;
+    let idx = match s.find(needle) {
+        Some(byte_idx) => s[..byte_idx].chars().count() as f64,
+        None => -1.0,
+    };
+
+    Ok(LiteralValue::Number(idx))
+}
+
+// The chars from `start` up to, but not including, `end`
+pub fn substring_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "substring")?;
+    let start = count_arg(args, 1, "substring")?;
+    let end = count_arg(args, 2, "substring")?;
+    let len = s.chars().count();
+    if start > end || end > len {
+        return Err(format!(
+            "substring range {start}..{end} out of bounds for string of length {len}"
+        ));
+    }
+
+    Ok(LiteralValue::StringValue(
+        s.chars().skip(start).take(end - start).collect(),
+    ))
+}
+
+pub fn repeat_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "repeat")?;
+    let count = count_arg(args, 1, "repeat")?;
+    Ok(LiteralValue::StringValue(s.repeat(count)))
+}
+
+pub fn lines_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "lines")?;
+    Ok(strings_to_list(s.lines()))
+}
+
+pub fn chars_impl(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    let s = string_arg(args, 0, "chars")?;
+    Ok(LiteralValue::from_list(
+        s.chars()
+            .map(|c| LiteralValue::StringValue(c.to_string()))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> LiteralValue {
+        LiteralValue::StringValue(s.to_string())
+    }
+
+    #[test]
+    fn unicode_indexes() {
+        let s = string("häßlich");
+        assert_eq!(
+            len_impl(std::slice::from_ref(&s)),
+            Ok(LiteralValue::Number(7.0))
+        );
+        assert_eq!(
+            index_of_impl(&[s.clone(), string("lich")]),
+            Ok(LiteralValue::Number(3.0))
+        );
+        assert_eq!(
+            substring_impl(&[
+                s.clone(),
+                LiteralValue::Number(1.0),
+                LiteralValue::Number(3.0)
+            ]),
+            Ok(string("äß"))
+        );
+        assert_eq!(upper_impl(std::slice::from_ref(&s)), Ok(string("HÄSSLICH")));
+        assert!(
+            substring_impl(&[s, LiteralValue::Number(2.0), LiteralValue::Number(8.0)]).is_err()
+        );
+    }
+
+    #[test]
+    fn split_and_join() {
+        let parts = split_impl(&[string("a,b,,c"), string(",")]).unwrap();
+        assert_eq!(parts.to_string(), r#"["a", "b", "", "c"]"#);
+        assert_eq!(join_impl(&[parts, string("-")]), Ok(string("a-b--c")));
+
+        let chars = split_impl(&[string("añb"), string("")]).unwrap();
+        assert_eq!(chars.to_string(), r#"["a", "ñ", "b"]"#);
+    }
+}
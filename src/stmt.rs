@@ -1,5 +1,7 @@
 use crate::expr::Expr;
+use crate::pattern::Pattern;
 use crate::scanner::Token;
+use std::borrow::Borrow;
 
 #[derive(Debug, Clone)]
 pub enum Stmt {
@@ -9,9 +11,18 @@ pub enum Stmt {
     Print {
         expression: Expr,
     },
+    // `constant` for `const`, which can't be assigned again
     Var {
         name: Token,
         initializer: Expr,
+        constant: bool,
+    },
+    // var [a, b] = pair;
+    Destructure {
+        keyword: Token,
+        pattern: Pattern,
+        initializer: Expr,
+        constant: bool,
     },
     Block {
         statements: Vec<Box<Stmt>>,
@@ -43,10 +54,38 @@ pub enum Stmt {
         keyword: Token,
         value: Option<Expr>,
     },
+    Test {
+        name: Token,
+        body: Vec<Stmt>,
+    },
+}
+
+fn declaration_keyword(constant: bool) -> &'static str {
+    if constant {
+        "const"
+    } else {
+        "var"
+    }
+}
+
+pub fn params_to_string(params: &[Token]) -> String {
+    params
+        .iter()
+        .map(|param| param.lexeme.clone())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// For both boxed statements and the plain ones of test blocks
+pub fn stmts_to_string<S: Borrow<Stmt>>(stmts: &[S]) -> String {
+    stmts
+        .iter()
+        .map(|stmt| stmt.borrow().to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
 impl Stmt {
-    #[allow(dead_code)]
     pub fn to_string(&self) -> String {
         use Stmt::*;
         match self {
@@ -54,33 +93,113 @@ impl Stmt {
             Print { expression } => format!("(print {})", expression.to_string()),
             Var {
                 name,
-                initializer: _,
-            } => format!("(var {})", name.lexeme),
-            Block { statements } => format!(
-                "(block {})",
-                statements
-                    .into_iter()
-                    .map(|stmt| stmt.to_string())
-                    .collect::<String>()
+                initializer,
+                constant,
+            } => format!(
+                "({} {} {})",
+                declaration_keyword(*constant),
+                name.lexeme,
+                initializer.to_string()
             ),
+            Destructure {
+                keyword: _,
+                pattern,
+                initializer,
+                constant,
+            } => format!(
+                "({} {} {})",
+                declaration_keyword(*constant),
+                pattern,
+                initializer.to_string()
+            ),
+            Block { statements } => format!("(block {})", stmts_to_string(statements)),
+            Class {
+                name,
+                methods,
+                superclass,
+            } => match superclass {
+                Some(superclass) => format!(
+                    "(class {} {} {})",
+                    name.lexeme,
+                    superclass.to_string(),
+                    stmts_to_string(methods)
+                ),
+                None => format!("(class {} {})", name.lexeme, stmts_to_string(methods)),
+            },
             IfStmt {
-                predicate: _,
-                then: _,
-                els: _,
-            } => todo!(),
-            WhileStmt {
-                condition: _,
-                body: _,
-            } => todo!(),
-            Function {
-                name: _,
-                params: _,
-                body: _,
-            } => todo!(),
-            CmdFunction { name: _, cmd: _ } => todo!(),
-            ReturnStmt { keyword: _, value: _ } => todo!(),
-            _ => todo!(),
+                predicate,
+                then,
+                els,
+            } => match els {
+                Some(els) => format!(
+                    "(if {} {} {})",
+                    predicate.to_string(),
+                    then.to_string(),
+                    els.to_string()
+                ),
+                None => format!("(if {} {})", predicate.to_string(), then.to_string()),
+            },
+            WhileStmt { condition, body } => {
+                format!("(while {} {})", condition.to_string(), body.to_string())
+            }
+            Function { name, params, body } => format!(
+                "(fun {} ({}) {})",
+                name.lexeme,
+                params_to_string(params),
+                stmts_to_string(body)
+            ),
+            CmdFunction { name, cmd } => format!("(cmd {} {:?})", name.lexeme, cmd),
+            ReturnStmt { keyword: _, value } => match value {
+                Some(value) => format!("(return {})", value.to_string()),
+                None => "(return)".to_string(),
+            },
+            Test { name, body } => format!("(test {} {})", name.lexeme, stmts_to_string(body)),
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    fn to_strings(source: &str) -> Vec<String> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let stmts = Parser::new(tokens).parse().unwrap();
+        stmts.iter().map(|stmt| stmt.to_string()).collect()
+    }
+
+    #[test]
+    fn statements_to_string() {
+        let source = "var a = 1;\n\
+                      fun f(x, y) { if (x < y) return x; else { print y; } }\n\
+                      while (a < 3) a = a + 1;\n\
+                      class B < A { m() { return this.x; } }\n\
+                      fun ls <- \"ls -la\";\n\
+                      test \"t\" { f(1, fun (z) { return; }); }";
+
+        assert_eq!(
+            to_strings(source),
+            vec![
+                "(var a 1)",
+                "(fun f (x y) (if (< (var x) (var y)) (return (var x)) (block (print (var y)))))",
+                "(while (< (var a) 3) (= a (+ (var a) 1)))",
+                "(class B (var A) (fun m () (return (get (this) x))))",
+                "(cmd ls \"ls -la\")",
+                "(test \"t\" (call (var f) 1 (fun (z) (return))))",
+            ]
+        );
+    }
+
+    #[test]
+    fn command_bodies_are_decoded() {
+        // The body is stored as the string value, whatever the literal looked like
+        assert_eq!(
+            to_strings("fun a <- r\"sed 's/\\t/ /'\"; fun b <- \"\"\"echo \"hi\" there\"\"\";"),
+            vec![
+                r#"(cmd a "sed 's/\\t/ /'")"#,
+                r#"(cmd b "echo \"hi\" there")"#,
+            ]
+        );
+    }
+}
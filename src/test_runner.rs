@@ -0,0 +1,461 @@
+use crate::interpreter::{Interpreter, TestResult};
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::stmt::Stmt;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const TEST_MARKER: &str = "// --- Test";
+const EXPECTED_MARKER: &str = "// --- Expected";
+// Starts the stderr lines that report test block results to `dena test`
+const RESULT_MARKER: &str = "test-result";
+
+// A test case file is made of the code to run, followed by an
+// "// --- Expected" line and the expected stdout, one "// "-prefixed
+// comment line per output line. Files without an expected section are run
+// for their `test "name" { ... }` blocks instead. Each file is run once, in
+// a `dena e` subprocess that also runs the test blocks where they appear
+// and reports their results on stderr, so the script's own output never
+// ends up in the report.
+struct TestCase {
+    path: PathBuf,
+    header: Vec<String>,
+    code: String,
+    expected: Option<Vec<String>>,
+}
+
+enum Outcome {
+    Passed,
+    Failed(String),
+    Blessed,
+}
+
+struct RunOutput {
+    stdout: Vec<String>,
+    results: Vec<TestResult>,
+    exit_code: Option<i32>,
+}
+
+impl TestCase {
+    fn parse(path: &Path, contents: &str) -> Self {
+        let lines = contents.split('\n').collect::<Vec<&str>>();
+
+        let mut header = vec![];
+        let mut code = vec![];
+        let mut expected = None;
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with(EXPECTED_MARKER) {
+                expected = Some(
+                    lines[i + 1..]
+                        .iter()
+                        .filter(|line| !line.is_empty())
+                        .map(|line| {
+                            let line = line.strip_prefix("//").unwrap_or(line);
+                            line.strip_prefix(' ').unwrap_or(line).to_string()
+                        })
+                        .collect::<Vec<String>>(),
+                );
+                break;
+            }
+
+            header.push(line.to_string());
+            if !line.starts_with(TEST_MARKER) {
+                code.push(*line);
+            }
+        }
+
+        Self {
+            path: path.to_path_buf(),
+            header,
+            code: code.join("\n"),
+            expected: expected.map(trim_trailing_empty),
+        }
+    }
+
+    fn run(&self) -> Result<RunOutput, String> {
+        let exe = env::current_exe().map_err(|e| e.to_string())?;
+        let output = Command::new(exe)
+            .arg("--test-blocks")
+            .arg("e")
+            .arg(&self.code)
+            .output()
+            .map_err(|e| format!("{}: could not run test: {}", self.path.display(), e))?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines = stdout.split('\n').map(|s| s.to_string()).collect();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok(RunOutput {
+            stdout: trim_trailing_empty(lines),
+            results: stderr.lines().filter_map(decode_result).collect(),
+            exit_code: output.status.code(),
+        })
+    }
+
+    fn check(&self, output: &[String]) -> Result<(), String> {
+        let name = self.path.display();
+        let expected = match &self.expected {
+            Some(expected) => expected,
+            None => {
+                return Err(format!(
+                    "{name}: No expected section in test case definition"
+                ))
+            }
+        };
+
+        if output.len() != expected.len() {
+            return Err(format!(
+                "{name}: output length does not match expected output: {} != {}\nFull output:\n{}",
+                output.len(),
+                expected.len(),
+                output.join("\n")
+            ));
+        }
+
+        for (line, expected) in output.iter().zip(expected.iter()) {
+            if line != expected.trim() {
+                return Err(format!(
+                    "{name}: {line} != {expected}\nFull output:\n{}",
+                    output.join("\n")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn bless(&self, output: &[String]) -> Result<(), String> {
+        let mut contents = self.header.join("\n");
+        if !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        contents.push_str(EXPECTED_MARKER);
+        contents.push('\n');
+        for line in output {
+            contents.push_str(&format!("// {line}\n"));
+        }
+
+        fs::write(&self.path, contents)
+            .map_err(|e| format!("{}: could not bless: {}", self.path.display(), e))
+    }
+
+    fn has_test_blocks(&self) -> bool {
+        let tokens = match Scanner::new(&self.code).scan_tokens() {
+            Ok(tokens) => tokens,
+            Err(_) => return false,
+        };
+        match Parser::new(tokens).parse() {
+            Ok(stmts) => stmts.iter().any(|stmt| matches!(stmt, Stmt::Test { .. })),
+            Err(_) => false,
+        }
+    }
+
+    // The outcome of the test blocks, and a line for each of them
+    fn check_test_blocks(&self, output: &RunOutput) -> (Outcome, Vec<String>) {
+        let name = self.path.display();
+        let mut lines = vec![];
+        let mut errors = vec![];
+        for result in &output.results {
+            let test_name = format!("test {}", result.name);
+            match &result.error {
+                None => lines.push(format!("    {test_name:.<81}...ok")),
+                Some(msg) => {
+                    lines.push(format!("    {test_name:.<81}...failed"));
+                    errors.push(format!(
+                        "{name}: {test_name} (line {}) failed:\n{msg}",
+                        result.line
+                    ));
+                }
+            }
+        }
+
+        // Only files without an expected section may stop with an error
+        if self.expected.is_none() && output.exit_code != Some(0) {
+            let error = output.stdout.iter().position(|line| line == "ERROR:");
+            errors.push(match (error, output.exit_code) {
+                (Some(idx), _) => format!("{name}: {}", output.stdout[idx + 1..].join("\n")),
+                (None, Some(code)) => format!("{name}: exited with code {code}"),
+                (None, None) => format!("{name}: was stopped by a signal"),
+            });
+        }
+
+        if errors.is_empty() {
+            (Outcome::Passed, lines)
+        } else {
+            (Outcome::Failed(errors.join("\n\n")), lines)
+        }
+    }
+
+    fn execute(&self, bless: bool) -> Result<(Outcome, Vec<String>), String> {
+        let output = self.run()?;
+        let (tests_outcome, lines) = self.check_test_blocks(&output);
+        if self.expected.is_none() && self.has_test_blocks() {
+            return Ok((tests_outcome, lines));
+        }
+
+        let outcome = match self.check(&output.stdout) {
+            Ok(_) => Outcome::Passed,
+            Err(_) if bless => {
+                self.bless(&output.stdout)?;
+                Outcome::Blessed
+            }
+            Err(msg) => return Ok((Outcome::Failed(msg), lines)),
+        };
+
+        match tests_outcome {
+            Outcome::Passed => Ok((outcome, lines)),
+            failed => Ok((failed, lines)),
+        }
+    }
+}
+
+// Test results are sent from the `dena e` subprocess as one line each, with
+// the tabs, line breaks and backslashes in their fields escaped
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some(other) => unescaped.push(other),
+            None => break,
+        }
+    }
+
+    unescaped
+}
+
+fn encode_result(result: &TestResult) -> String {
+    let error = result.error.as_deref().map(escape).unwrap_or_default();
+    format!(
+        "{RESULT_MARKER}\t{}\t{}\t{error}",
+        result.line,
+        escape(&result.name)
+    )
+}
+
+fn decode_result(line: &str) -> Option<TestResult> {
+    let mut fields = line
+        .strip_prefix(RESULT_MARKER)?
+        .strip_prefix('\t')?
+        .split('\t');
+    let line = fields.next()?.parse().ok()?;
+    let name = unescape(fields.next()?);
+    let error = fields.next()?;
+    Some(TestResult {
+        name,
+        line,
+        error: (!error.is_empty()).then(|| unescape(error)),
+    })
+}
+
+/// Runs `code` like `dena e` does, but also runs its test blocks and reports
+/// their results on stderr for the `dena test` process that started it.
+pub fn run_with_test_blocks(code: &str) -> Result<(), String> {
+    let mut interpreter = Interpreter::for_tests();
+    interpreter.set_script_args(None, &[])?;
+    let result = crate::run(&mut interpreter, code);
+    for test_result in interpreter.test_results.take().unwrap_or_default() {
+        eprintln!("{}", encode_result(&test_result));
+    }
+
+    result
+}
+
+fn trim_trailing_empty(mut lines: Vec<String>) -> Vec<String> {
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    lines
+}
+
+fn collect_cases(path: &Path, cases: &mut Vec<PathBuf>) -> Result<(), String> {
+    if path.is_file() {
+        cases.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut entries = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<PathBuf>>();
+    entries.sort();
+
+    for entry in entries {
+        let file_name = entry
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if file_name.starts_with('.') || file_name.contains('~') {
+            continue;
+        }
+
+        if entry.is_dir() {
+            collect_cases(&entry, cases)?;
+        } else if entry.extension().is_some_and(|ext| ext == "dena") {
+            cases.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs every test case found under `paths` (files or directories searched
+/// recursively for `.dena` files) and prints a summary. With `bless`, the
+/// expected section of failing output cases is rewritten with the actual
+/// output. Returns whether all cases passed.
+pub fn run_tests(paths: &[String], bless: bool) -> Result<bool, String> {
+    let mut files = vec![];
+    if paths.is_empty() {
+        collect_cases(Path::new("."), &mut files)?;
+    }
+    for path in paths {
+        collect_cases(Path::new(path), &mut files)?;
+    }
+
+    let mut errors = vec![];
+    let mut passed = 0;
+    let mut blessed = 0;
+    for file in &files {
+        let name = file.display().to_string();
+        let contents =
+            fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        let case = TestCase::parse(file, &contents);
+
+        let (outcome, lines) = case.execute(bless)?;
+        match outcome {
+            Outcome::Passed => {
+                passed += 1;
+                println!("Running {name:.<85}...ok");
+            }
+            Outcome::Blessed => {
+                blessed += 1;
+                println!("Running {name:.<85}...blessed");
+            }
+            Outcome::Failed(msg) => {
+                errors.push(msg);
+                println!("Running {name:.<85}...failed");
+            }
+        }
+        for line in lines {
+            println!("{line}");
+        }
+    }
+
+    if !errors.is_empty() {
+        println!("\nErrors:\n\n{}", errors.join("\n\n"));
+    }
+
+    println!(
+        "\nRan {} tests: {} passed, {} failed, {} blessed",
+        files.len(),
+        passed,
+        errors.len(),
+        blessed
+    );
+
+    Ok(errors.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_case_sections() {
+        let contents = "// --- Test\nprint 1;\nprint \"\";\n\n// --- Expected\n// 1\n// \n";
+        let case = TestCase::parse(Path::new("case.dena"), contents);
+
+        assert_eq!(case.code, "print 1;\nprint \"\";\n");
+        assert_eq!(case.expected, Some(vec!["1".to_string()]));
+        assert!(case.check(&["1".to_string()]).is_ok());
+        assert!(case.check(&["2".to_string()]).is_err());
+    }
+
+    #[test]
+    fn check_test_blocks() {
+        let contents = "fun double(x) { return x * 2; }\n\
+                        test \"double\" { assertEqual(double(2), 4); }\n";
+        let case = TestCase::parse(Path::new("case.dena"), contents);
+        assert!(case.has_test_blocks());
+
+        let broken = TestResult {
+            name: "\"broken\ttest\"".to_string(),
+            line: 3,
+            error: Some("Line 4: Assertion failed: not\nfive \\n".to_string()),
+        };
+        let passed = TestResult {
+            name: "\"double\"".to_string(),
+            line: 2,
+            error: None,
+        };
+        let stderr = format!(
+            "{}\nnoise\n{}\n",
+            encode_result(&passed),
+            encode_result(&broken)
+        );
+        let output = RunOutput {
+            stdout: vec!["\"script output\"".to_string()],
+            results: stderr.lines().filter_map(decode_result).collect(),
+            exit_code: Some(0),
+        };
+        assert_eq!(output.results.len(), 2);
+
+        match case.check_test_blocks(&output) {
+            (Outcome::Failed(msg), lines) => {
+                assert!(!msg.contains("\"double\""));
+                assert!(msg.contains("test \"broken\ttest\" (line 3) failed"));
+                assert!(msg.contains("Line 4: Assertion failed: not\nfive \\n"));
+                assert_eq!(lines.len(), 2);
+                assert!(lines[0].starts_with("    test \"double\"...") && lines[0].ends_with("ok"));
+                assert!(lines.iter().all(|line| !line.contains("script output")));
+            }
+            _ => panic!("Should have failed"),
+        }
+    }
+
+    #[test]
+    fn test_blocks_file_that_stops() {
+        let case = TestCase::parse(Path::new("case.dena"), "test \"a\" {}\nexit(3);\n");
+        let output = RunOutput {
+            stdout: vec![],
+            results: vec![],
+            exit_code: Some(3),
+        };
+        match case.check_test_blocks(&output) {
+            (Outcome::Failed(msg), _) => assert_eq!(msg, "case.dena: exited with code 3"),
+            _ => panic!("Should have failed"),
+        }
+
+        let output = RunOutput {
+            stdout: vec!["ERROR:".to_string(), "Line 2: oops".to_string()],
+            results: vec![],
+            exit_code: Some(1),
+        };
+        match case.check_test_blocks(&output) {
+            (Outcome::Failed(msg), _) => assert_eq!(msg, "case.dena: Line 2: oops"),
+            _ => panic!("Should have failed"),
+        }
+    }
+
+    #[test]
+    fn parse_case_without_expected() {
+        let case = TestCase::parse(Path::new("case.dena"), "print 1;\n");
+
+        assert_eq!(case.expected, None);
+        assert!(case.check(&["1".to_string()]).is_err());
+    }
+}